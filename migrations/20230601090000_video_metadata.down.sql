@@ -0,0 +1,11 @@
+-- Add down migration script here
+
+ALTER TABLE remoteurl DROP COLUMN channel_name;
+
+ALTER TABLE remoteurl DROP COLUMN channel_id;
+
+ALTER TABLE remoteurl DROP COLUMN duration_secs;
+
+ALTER TABLE remoteurl DROP COLUMN publish_date;
+
+ALTER TABLE remoteurl DROP COLUMN view_count;
\ No newline at end of file
@@ -0,0 +1,11 @@
+-- Add up migration script here
+
+ALTER TABLE remoteurl ADD COLUMN channel_name VARCHAR;
+
+ALTER TABLE remoteurl ADD COLUMN channel_id VARCHAR;
+
+ALTER TABLE remoteurl ADD COLUMN duration_secs BIGINT;
+
+ALTER TABLE remoteurl ADD COLUMN publish_date VARCHAR;
+
+ALTER TABLE remoteurl ADD COLUMN view_count BIGINT;
\ No newline at end of file
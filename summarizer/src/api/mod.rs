@@ -1,12 +1,14 @@
 mod v1;
+use crate::ConfigLimits;
 use axum::{
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use sqlx::postgres::PgPool;
 use tower_http::services::{ServeDir, ServeFile};
 use v1::{summaries, summarize};
-pub fn get_router(pgpool: PgPool) -> Router {
+/// router of the api, the configs sent along jobs are checked against the limits.
+pub fn get_router(pgpool: PgPool, limits: ConfigLimits) -> Router {
     Router::new()
         .route("/", get(|| async { "Summarizer" }))
         .nest(
@@ -23,4 +25,5 @@ pub fn get_router(pgpool: PgPool) -> Router {
                     .with_state(pgpool),
             ),
         )
+        .layer(Extension(limits))
 }
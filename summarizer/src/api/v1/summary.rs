@@ -1,12 +1,14 @@
 use crate::database::Postgresmethods;
 use crate::error::Serror;
 use crate::scheduler::Youtubelink;
+use crate::{ConfigLimits, SummarizerConfig};
 use apalis::postgres::PostgresStorage;
 use apalis::prelude::Storage;
-use axum::extract::{Json, State};
+use axum::extract::{Extension, Json, State};
 use serde::Deserialize;
 use serde_json;
 use sqlx::postgres::PgPool;
+use youtube_transcript::VideoId;
 pub(crate) async fn summaries(State(pg): State<PgPool>) -> Result<Json<serde_json::Value>, Serror> {
     let pg = Postgresmethods::new(&pg);
     let summaries = pg.get_summaries().await?;
@@ -16,14 +18,25 @@ pub(crate) async fn summaries(State(pg): State<PgPool>) -> Result<Json<serde_jso
 #[derive(Deserialize)]
 pub struct Link {
     link: String,
+    /// summarizes with the worker's config if not set.
+    #[serde(default)]
+    config: Option<SummarizerConfig>,
 }
 
 pub(crate) async fn summarize(
     State(pg): State<PgPool>,
+    Extension(limits): Extension<ConfigLimits>,
     Json(link): Json<Link>,
 ) -> Result<Json<serde_json::Value>, Serror> {
+    let video_id = link.link.parse::<VideoId>()?;
+    if let Some(config) = &link.config {
+        limits.check(config)?;
+    }
     let mut storage: PostgresStorage<Youtubelink> = PostgresStorage::new(pg);
-    let you = Youtubelink(link.link.clone());
+    let you = Youtubelink {
+        link: video_id.watch_url(),
+        config: link.config,
+    };
     let _job = storage
         .push(you)
         .await
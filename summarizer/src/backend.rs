@@ -0,0 +1,312 @@
+use crate::error::Serror;
+use crate::utils::env_var;
+use async_openai::{
+    types::{
+        ChatCompletionRequestMessageArgs, CreateChatCompletionRequestArgs, Role as OpenAIRole,
+    },
+    Client,
+};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+/// Chat completion api the summaries are generated with.
+pub trait ChatBackend: Send + Sync {
+    /// content of the reply to the request.
+    fn chat(&self, request: ChatRequest) -> BoxFuture<'_, Result<String, Serror>>;
+}
+
+/// Chat completions of the hosted OpenAI api, authenticated by the `OPENAI_API_KEY` environment variable.
+#[derive(Clone, Default)]
+pub struct OpenAIChat {
+    client: Client,
+}
+
+impl OpenAIChat {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl ChatBackend for OpenAIChat {
+    fn chat(&self, request: ChatRequest) -> BoxFuture<'_, Result<String, Serror>> {
+        Box::pin(async move {
+            let messages = request
+                .messages
+                .iter()
+                .map(|x| {
+                    let role = match x.role {
+                        Role::System => OpenAIRole::System,
+                        Role::User => OpenAIRole::User,
+                        Role::Assistant => OpenAIRole::Assistant,
+                    };
+                    ChatCompletionRequestMessageArgs::default()
+                        .role(role)
+                        .content(&x.content)
+                        .build()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut args = CreateChatCompletionRequestArgs::default();
+            args.model(request.model).messages(messages);
+            if let Some(temperature) = request.temperature {
+                args.temperature(temperature);
+            }
+            if let Some(max_tokens) = request.max_tokens {
+                let max_tokens = u16::try_from(max_tokens).map_err(|_| {
+                    Serror::InvalidConfig(format!(
+                        "max_tokens {} is above the {} supported by the OpenAI client",
+                        max_tokens,
+                        u16::MAX
+                    ))
+                })?;
+                args.max_tokens(max_tokens);
+            }
+            let request = args.build()?;
+            self.client
+                .chat()
+                .create(request)
+                .await?
+                .choices
+                .into_iter()
+                .next()
+                .map(|x| x.message.content)
+                .ok_or(Serror::Other("cannot find summary".to_string()))
+        })
+    }
+}
+
+/// Chat completions of any server implementing the OpenAI `/chat/completions` api, ex: llama.cpp or Ollama.
+#[derive(Clone)]
+pub struct OpenAICompatibleChat {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CompletionResponse {
+    choices: Vec<CompletionChoice>,
+}
+
+#[derive(Deserialize)]
+struct CompletionChoice {
+    message: CompletionMessage,
+}
+
+#[derive(Deserialize)]
+struct CompletionMessage {
+    content: String,
+}
+
+impl OpenAICompatibleChat {
+    /// base url of the api, ex: "http://localhost:11434/v1"
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            model: None,
+        }
+    }
+
+    /// bearer token sent with every request, local servers usually don't need one.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// model served by the server, used instead of the model of the requests. Ex: "llama3"
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    fn content(response: &str) -> Result<String, Serror> {
+        let response: CompletionResponse = serde_json::from_str(response)?;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|x| x.message.content)
+            .ok_or(Serror::Other("cannot find summary".to_string()))
+    }
+}
+
+impl ChatBackend for OpenAICompatibleChat {
+    fn chat(&self, mut request: ChatRequest) -> BoxFuture<'_, Result<String, Serror>> {
+        Box::pin(async move {
+            if let Some(model) = &self.model {
+                request.model = model.clone();
+            }
+            let mut builder = self
+                .client
+                .post(format!("{}/chat/completions", self.base_url))
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_string(&request)?);
+            if let Some(api_key) = &self.api_key {
+                builder = builder.bearer_auth(api_key);
+            }
+            let response = builder.send().await?.error_for_status()?.text().await?;
+            Self::content(&response)
+        })
+    }
+}
+
+/// Deterministic backend for tests, replies with the first words of the last message without any network call.
+#[derive(Clone)]
+pub struct FakeChat {
+    words: usize,
+    requests: Arc<AtomicUsize>,
+}
+
+impl Default for FakeChat {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl FakeChat {
+    pub fn new(words: usize) -> Self {
+        Self {
+            words,
+            requests: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// number of requests replied so far, shared by the clones.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+impl ChatBackend for FakeChat {
+    fn chat(&self, request: ChatRequest) -> BoxFuture<'_, Result<String, Serror>> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        let reply = request
+            .messages
+            .last()
+            .map(|x| {
+                x.content
+                    .split_whitespace()
+                    .take(self.words)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+        Box::pin(async move { Ok(reply) })
+    }
+}
+
+/// backend chosen by the environment: an OpenAI compatible server at `SUMMARIZER_API_BASE` if set,
+/// with the optional `SUMMARIZER_API_KEY`, else the hosted OpenAI api.
+pub fn from_env() -> Arc<dyn ChatBackend> {
+    match env_var("SUMMARIZER_API_BASE") {
+        Ok(base_url) => {
+            log::info!("Using the OpenAI compatible api at {}", base_url);
+            let mut backend = OpenAICompatibleChat::new(base_url);
+            if let Ok(api_key) = env_var("SUMMARIZER_API_KEY") {
+                backend = backend.api_key(api_key);
+            }
+            Arc::new(backend)
+        }
+        Err(_) => Arc::new(OpenAIChat::default()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fake_chat() {
+        let fake = FakeChat::new(3);
+        let request = ChatRequest {
+            model: "fake".to_string(),
+            messages: vec![
+                ChatMessage::system("summarize"),
+                ChatMessage::user("one two three four"),
+            ],
+            temperature: None,
+            max_tokens: None,
+        };
+        assert_eq!(fake.chat(request.clone()).await.unwrap(), "one two three");
+        assert_eq!(fake.clone().chat(request).await.unwrap(), "one two three");
+        assert_eq!(fake.requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_openai_max_tokens() {
+        let request = ChatRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![ChatMessage::user("text")],
+            temperature: None,
+            max_tokens: Some(70_000),
+        };
+        assert!(matches!(
+            OpenAIChat::default().chat(request).await,
+            Err(Serror::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_compatible_response() {
+        let response = r#"{"id":"chatcmpl-1","object":"chat.completion","choices":[{"index":0,"message":{"role":"assistant","content":"A summary."},"finish_reason":"stop"}]}"#;
+        assert_eq!(
+            OpenAICompatibleChat::content(response).unwrap(),
+            "A summary."
+        );
+        assert!(OpenAICompatibleChat::content(r#"{"choices":[]}"#).is_err());
+        let request = ChatRequest {
+            model: "llama3".to_string(),
+            messages: vec![ChatMessage::user("text")],
+            temperature: None,
+            max_tokens: Some(200),
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"model":"llama3","messages":[{"role":"user","content":"text"}],"max_tokens":200}"#
+        );
+    }
+}
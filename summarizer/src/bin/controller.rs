@@ -1,7 +1,6 @@
 use summarizer::error::Serror;
 use summarizer::scheduler::setup_youtube_data_workers;
 use summarizer::utils::env_var;
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), Serror> {
@@ -3,7 +3,6 @@ use apalis::prelude::Storage;
 use summarizer::error::Serror;
 use summarizer::scheduler::Youtubelink;
 use summarizer::utils::env_var;
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), Serror> {
@@ -20,8 +19,8 @@ async fn main() -> Result<(), Serror> {
     ];
     let mut storage: PostgresStorage<Youtubelink> = PostgresStorage::connect(postgres_url).await?;
     for link in links {
-        let you = Youtubelink(link.to_string());
-        let _job = storage
+        let you = Youtubelink::new(link);
+        storage
             .push(you)
             .await
             .map_err(|_| Serror::Database("asdf".to_string()))?;
@@ -1,10 +1,9 @@
-use axum;
 use sqlx::postgres::PgPool;
 use std::net::SocketAddr;
 use summarizer::api;
 use summarizer::utils::env_var;
-use tokio;
-use tower_http::cors::{Any, CorsLayer};
+use summarizer::ConfigLimits;
+use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 #[tokio::main]
 async fn main() -> Result<(), String> {
@@ -20,7 +19,8 @@ async fn main() -> Result<(), String> {
         .map_err(|x| x.to_string())?;
 
     // Setup server
-    let app = api::get_router(pg);
+    let limits = ConfigLimits::from_env().map_err(|x| x.to_string())?;
+    let app = api::get_router(pg, limits);
     let cors = CorsLayer::permissive();
     let addr = SocketAddr::from(([0, 0, 0, 0], 3001));
     tracing::debug!("listening on {}", addr);
@@ -0,0 +1,458 @@
+use crate::default;
+use crate::error::Serror;
+use crate::summarize::SummaryStrategy;
+use crate::tokenize::{ChunkStrategy, ModelTokenizer, TokenizerRegistry};
+use crate::utils::env_var;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// placeholder of the prompt templates replaced by the text to summarize.
+pub const TEXT_PLACEHOLDER: &str = "{text}";
+/// placeholder of the refine prompt replaced by the summary of the previous chunks.
+pub const SUMMARY_PLACEHOLDER: &str = "{summary}";
+
+/// Model, prompts and generation parameters of the summaries.
+///
+/// Fields missing from a TOML file or a job take their default values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummarizerConfig {
+    pub(crate) model: String,
+    pub(crate) system_prompt: String,
+    /// template of the user message summarizing a chunk of the content.
+    pub(crate) chunk_prompt: String,
+    /// template of the user message merging the summaries of the chunks.
+    pub(crate) reduce_prompt: String,
+    /// template of the user message refining the summary of the previous chunks with the next chunk.
+    pub(crate) refine_prompt: String,
+    pub(crate) temperature: Option<f32>,
+    pub(crate) max_tokens: Option<u32>,
+    pub(crate) chunk_strategy: ChunkStrategy,
+    /// tokens of the end of a chunk repeated at the start of the next one.
+    pub(crate) chunk_overlap: usize,
+    pub(crate) strategy: SummaryStrategy,
+    /// passes merging the summaries of the chunks before giving up, for the map-reduce strategy.
+    pub(crate) max_reduce_depth: usize,
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        Self {
+            model: default::GPT_MODEL.to_string(),
+            system_prompt: default::SYSTEM_PROMPT.to_string(),
+            chunk_prompt: default::CHUNK_PROMPT.to_string(),
+            reduce_prompt: default::REDUCE_PROMPT.to_string(),
+            refine_prompt: default::REFINE_PROMPT.to_string(),
+            temperature: None,
+            max_tokens: None,
+            chunk_strategy: ChunkStrategy::default(),
+            chunk_overlap: 0,
+            strategy: SummaryStrategy::default(),
+            max_reduce_depth: default::MAX_REDUCE_DEPTH,
+        }
+    }
+}
+
+/// fills the template with the text, appending the text when the template has no placeholder.
+fn render(template: &str, text: &str) -> String {
+    if template.contains(TEXT_PLACEHOLDER) {
+        template.replace(TEXT_PLACEHOLDER, text)
+    } else {
+        format!("{template}\n\n{text}")
+    }
+}
+
+impl SummarizerConfig {
+    pub fn builder() -> SummarizerConfigBuilder {
+        SummarizerConfigBuilder::default()
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub(crate) fn chunk_message(&self, text: &str) -> String {
+        render(&self.chunk_prompt, text)
+    }
+
+    pub(crate) fn reduce_message(&self, text: &str) -> String {
+        render(&self.reduce_prompt, text)
+    }
+
+    /// the summary is put before the template when it has no placeholder.
+    pub(crate) fn refine_message(&self, summary: &str, text: &str) -> String {
+        let template = if self.refine_prompt.contains(SUMMARY_PLACEHOLDER) {
+            self.refine_prompt.replace(SUMMARY_PLACEHOLDER, summary)
+        } else {
+            format!("{summary}\n\n{}", self.refine_prompt)
+        };
+        render(&template, text)
+    }
+
+    /// tokens of text fitting in a request along the system prompt, the message template and the output.
+    pub(crate) fn chunk_size(
+        &self,
+        tokenizer: &ModelTokenizer,
+        template: &str,
+    ) -> Result<usize, Serror> {
+        let prompt = format!("{}\n{}", self.system_prompt, template);
+        tokenizer.chunk_size(&prompt, self.max_tokens)
+    }
+
+    /// tokens of the chunks the content is cut into, refine leaves room for the running summary.
+    pub(crate) fn content_chunk_size(&self, tokenizer: &ModelTokenizer) -> Result<usize, Serror> {
+        match self.strategy {
+            SummaryStrategy::Refine => {
+                // at most as long as the output
+                let summary_tokens = self
+                    .max_tokens
+                    .map_or(tokenizer.spec().output_tokens, |x| x as usize);
+                self.chunk_size(tokenizer, &self.refine_message("", ""))?
+                    .checked_sub(summary_tokens)
+                    .filter(|x| *x > 0)
+                    .ok_or(Serror::Tokenize(
+                        "the refine prompt and the summary don't fit in the context window"
+                            .to_string(),
+                    ))
+            }
+            _ => self.chunk_size(tokenizer, &self.chunk_message("")),
+        }
+    }
+
+    /// checks the config can summarize with the spec of its model in the registry.
+    pub fn validate(&self, registry: &TokenizerRegistry) -> Result<(), Serror> {
+        let invalid = |x: String| Err(Serror::InvalidConfig(x));
+        if self.max_reduce_depth == 0 {
+            return invalid("max_reduce_depth must be at least 1".to_string());
+        }
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return invalid(format!("temperature {} is not within 0 and 2", temperature));
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return invalid("max_tokens must be at least 1".to_string());
+        }
+        let tokenizer = registry.tokenizer(&self.model)?;
+        // the prompts and the output must leave room for the text in the context window
+        let chunk_size = self
+            .chunk_size(&tokenizer, &self.reduce_message(""))
+            .and_then(|_| self.content_chunk_size(&tokenizer))
+            .map_err(|x| Serror::InvalidConfig(x.to_string()))?;
+        if self.chunk_overlap >= chunk_size {
+            return invalid(format!(
+                "chunk_overlap {} is not less than the {} tokens of a chunk",
+                self.chunk_overlap, chunk_size
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, Serror> {
+        toml::from_str(text).map_err(|x| Serror::InvalidConfig(x.to_string()))
+    }
+
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, Serror> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// config of the TOML file at `SUMMARIZER_CONFIG` if set, overridden by the `SUMMARIZER_MODEL`,
+    /// `SUMMARIZER_SYSTEM_PROMPT`, `SUMMARIZER_CHUNK_PROMPT`, `SUMMARIZER_REDUCE_PROMPT`, `SUMMARIZER_REFINE_PROMPT`,
+    /// `SUMMARIZER_TEMPERATURE`, `SUMMARIZER_MAX_TOKENS`, `SUMMARIZER_CHUNK_STRATEGY`, `SUMMARIZER_CHUNK_OVERLAP`,
+    /// `SUMMARIZER_STRATEGY` and `SUMMARIZER_MAX_REDUCE_DEPTH` environment variables.
+    pub fn from_env() -> Result<Self, Serror> {
+        let config = match env_var("SUMMARIZER_CONFIG") {
+            Ok(path) => {
+                log::info!("Loading Summarizer config from {}", path);
+                Self::from_toml_file(path)?
+            }
+            Err(_) => Self::default(),
+        };
+        let mut builder = SummarizerConfigBuilder { config };
+        if let Ok(model) = env_var("SUMMARIZER_MODEL") {
+            builder = builder.model(model);
+        }
+        if let Ok(prompt) = env_var("SUMMARIZER_SYSTEM_PROMPT") {
+            builder = builder.system_prompt(prompt);
+        }
+        if let Ok(prompt) = env_var("SUMMARIZER_CHUNK_PROMPT") {
+            builder = builder.chunk_prompt(prompt);
+        }
+        if let Ok(prompt) = env_var("SUMMARIZER_REDUCE_PROMPT") {
+            builder = builder.reduce_prompt(prompt);
+        }
+        if let Ok(prompt) = env_var("SUMMARIZER_REFINE_PROMPT") {
+            builder = builder.refine_prompt(prompt);
+        }
+        if let Ok(temperature) = env_var("SUMMARIZER_TEMPERATURE") {
+            let temperature = temperature.parse().map_err(|_| {
+                Serror::Environment(format!("invalid SUMMARIZER_TEMPERATURE: {}", temperature))
+            })?;
+            builder = builder.temperature(temperature);
+        }
+        if let Ok(max_tokens) = env_var("SUMMARIZER_MAX_TOKENS") {
+            let max_tokens = max_tokens.parse().map_err(|_| {
+                Serror::Environment(format!("invalid SUMMARIZER_MAX_TOKENS: {}", max_tokens))
+            })?;
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Ok(strategy) = env_var("SUMMARIZER_CHUNK_STRATEGY") {
+            builder = builder.chunk_strategy(strategy.parse()?);
+        }
+        if let Ok(overlap) = env_var("SUMMARIZER_CHUNK_OVERLAP") {
+            let overlap = overlap.parse().map_err(|_| {
+                Serror::Environment(format!("invalid SUMMARIZER_CHUNK_OVERLAP: {}", overlap))
+            })?;
+            builder = builder.chunk_overlap(overlap);
+        }
+        if let Ok(strategy) = env_var("SUMMARIZER_STRATEGY") {
+            builder = builder.strategy(strategy.parse()?);
+        }
+        if let Ok(depth) = env_var("SUMMARIZER_MAX_REDUCE_DEPTH") {
+            let depth = depth.parse().map_err(|_| {
+                Serror::Environment(format!("invalid SUMMARIZER_MAX_REDUCE_DEPTH: {}", depth))
+            })?;
+            builder = builder.max_reduce_depth(depth);
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Limits of the configs sent by API callers along their jobs, keeping them from using costly models
+/// or unbounded requests.
+#[derive(Clone)]
+pub struct ConfigLimits {
+    models: Vec<String>,
+    max_reduce_depth: usize,
+    registry: TokenizerRegistry,
+}
+
+impl ConfigLimits {
+    /// allows the models only, with at most the default reduce depth.
+    pub fn new(models: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            models: models.into_iter().map(Into::into).collect(),
+            max_reduce_depth: default::MAX_REDUCE_DEPTH,
+            registry: TokenizerRegistry::default(),
+        }
+    }
+
+    /// set the reduce passes a job can ask for.
+    pub fn max_reduce_depth(mut self, depth: usize) -> Self {
+        self.max_reduce_depth = depth;
+        self
+    }
+
+    /// set the registry the configs are validated with, see [`SummarizerConfig::validate`]
+    pub fn registry(mut self, registry: TokenizerRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// allows the comma separated models of `SUMMARIZER_API_MODELS`, only the model of the worker's config if not set.
+    pub fn from_env() -> Result<Self, Serror> {
+        let models = match env_var("SUMMARIZER_API_MODELS") {
+            Ok(models) => models
+                .split(',')
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(String::from)
+                .collect(),
+            Err(_) => vec![SummarizerConfig::from_env()?.model],
+        };
+        Ok(Self::new(models))
+    }
+
+    /// checks the config of a job is allowed and valid.
+    pub fn check(&self, config: &SummarizerConfig) -> Result<(), Serror> {
+        if !self.models.contains(&config.model) {
+            return Err(Serror::InvalidConfig(format!(
+                "model {} is not allowed, use one of: {}",
+                config.model,
+                self.models.join(", ")
+            )));
+        }
+        if config.max_reduce_depth > self.max_reduce_depth {
+            return Err(Serror::InvalidConfig(format!(
+                "max_reduce_depth {} is more than {}",
+                config.max_reduce_depth, self.max_reduce_depth
+            )));
+        }
+        let output_tokens = self.registry.spec(&config.model).output_tokens;
+        if config
+            .max_tokens
+            .is_some_and(|x| x as usize > output_tokens)
+        {
+            return Err(Serror::InvalidConfig(format!(
+                "max_tokens is more than the {} output tokens of {}",
+                output_tokens, config.model
+            )));
+        }
+        config.validate(&self.registry)
+    }
+}
+
+/// Builder struct for building [`SummarizerConfig`]
+#[derive(Default)]
+pub struct SummarizerConfigBuilder {
+    config: SummarizerConfig,
+}
+
+impl SummarizerConfigBuilder {
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.config.model = model.into();
+        self
+    }
+
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.config.system_prompt = prompt.into();
+        self
+    }
+
+    /// template of the message summarizing each chunk, `{text}` is replaced by the chunk.
+    pub fn chunk_prompt(mut self, template: impl Into<String>) -> Self {
+        self.config.chunk_prompt = template.into();
+        self
+    }
+
+    /// template of the message merging summaries, `{text}` is replaced by the summaries.
+    pub fn reduce_prompt(mut self, template: impl Into<String>) -> Self {
+        self.config.reduce_prompt = template.into();
+        self
+    }
+
+    /// template of the message refining the summary with the next chunk, `{summary}` is replaced by the
+    /// summary of the previous chunks and `{text}` by the chunk.
+    pub fn refine_prompt(mut self, template: impl Into<String>) -> Self {
+        self.config.refine_prompt = template.into();
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.config.temperature = Some(temperature);
+        self
+    }
+
+    /// maximum number of tokens generated for each request.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.config.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// set how the content is cut into chunks, see [`ChunkStrategy`]
+    pub fn chunk_strategy(mut self, strategy: ChunkStrategy) -> Self {
+        self.config.chunk_strategy = strategy;
+        self
+    }
+
+    /// set the tokens of the end of a chunk repeated at the start of the next one.
+    pub fn chunk_overlap(mut self, overlap: usize) -> Self {
+        self.config.chunk_overlap = overlap;
+        self
+    }
+
+    /// set how the chunks are summarized, see [`SummaryStrategy`]
+    pub fn strategy(mut self, strategy: SummaryStrategy) -> Self {
+        self.config.strategy = strategy;
+        self
+    }
+
+    /// set the passes merging the summaries of the map-reduce strategy before failing.
+    pub fn max_reduce_depth(mut self, depth: usize) -> Self {
+        self.config.max_reduce_depth = depth;
+        self
+    }
+
+    pub fn build(self) -> SummarizerConfig {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_builder() {
+        let config = SummarizerConfig::builder()
+            .model("llama3")
+            .chunk_prompt("Summarize:\n{text}")
+            .reduce_prompt("Merge the summaries")
+            .temperature(0.2)
+            .max_tokens(300)
+            .build();
+        assert_eq!(config.model(), "llama3");
+        assert_eq!(config.chunk_message("abc"), "Summarize:\nabc");
+        assert_eq!(config.reduce_message("abc"), "Merge the summaries\n\nabc");
+        assert_eq!(config.temperature, Some(0.2));
+        assert_eq!(config.system_prompt, default::SYSTEM_PROMPT);
+        let config = SummarizerConfig::builder()
+            .refine_prompt("Summary: {summary}\nText:")
+            .build();
+        assert_eq!(config.refine_message("a", "b"), "Summary: a\nText:\n\nb");
+    }
+
+    #[test]
+    fn test_config_validate() {
+        let registry = TokenizerRegistry::default();
+        assert!(SummarizerConfig::default().validate(&registry).is_ok());
+        for config in [
+            SummarizerConfig::builder().max_reduce_depth(0).build(),
+            SummarizerConfig::builder().chunk_overlap(20_000).build(),
+            SummarizerConfig::builder().temperature(3.0).build(),
+            SummarizerConfig::builder().max_tokens(20_000).build(),
+            SummarizerConfig::builder()
+                .strategy(SummaryStrategy::Refine)
+                .max_tokens(9_000)
+                .build(),
+        ] {
+            let result = config.validate(&registry);
+            assert!(
+                matches!(result, Err(Serror::InvalidConfig(_))),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_limits() {
+        let limits = ConfigLimits::new(["gpt-4o-mini"]).max_reduce_depth(2);
+        let config = SummarizerConfig::builder().model("gpt-4o-mini");
+        assert!(limits.check(&config.max_reduce_depth(2).build()).is_ok());
+        let config = SummarizerConfig::builder().model("gpt-4o-mini");
+        assert!(limits.check(&config.max_reduce_depth(3).build()).is_err());
+        let config = SummarizerConfig::builder().model("gpt-4o-mini");
+        assert!(limits.check(&config.max_tokens(100_000).build()).is_err());
+        let config = SummarizerConfig::builder().model("o1");
+        assert!(limits.check(&config.build()).is_err());
+    }
+
+    #[test]
+    fn test_config_toml() {
+        let config = SummarizerConfig::from_toml(
+            r#"
+            model = "gpt-4o-mini"
+            max_tokens = 500
+            reduce_prompt = "Combine: {text}"
+            chunk_strategy = "paragraphs"
+            chunk_overlap = 100
+            strategy = "refine"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.model, "gpt-4o-mini");
+        assert_eq!(config.max_tokens, Some(500));
+        assert_eq!(config.reduce_message("a"), "Combine: a");
+        assert_eq!(config.chunk_prompt, default::CHUNK_PROMPT);
+        assert_eq!(config.temperature, None);
+        assert_eq!(config.chunk_strategy, ChunkStrategy::Paragraphs);
+        assert_eq!(config.chunk_overlap, 100);
+        assert_eq!(config.strategy, SummaryStrategy::Refine);
+        assert_eq!(config.max_reduce_depth, default::MAX_REDUCE_DEPTH);
+        assert!(SummarizerConfig::from_toml("max_tokens = \"many\"").is_err());
+        assert!(matches!(
+            SummarizerConfig::from_toml("strategy = \"one_shot\""),
+            Err(Serror::InvalidConfig(_))
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-mod database;
 mod models;
+mod postgres;
 
-pub(crate) use database::Postgresmethods;
+pub(crate) use postgres::Postgresmethods;
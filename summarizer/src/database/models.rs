@@ -4,24 +4,11 @@ use sqlx::FromRow;
 #[derive(FromRow)]
 pub(crate) struct Remoteurl {
     pub id: i32,
-    pub created_at: NaiveDateTime,
-    pub link: String,
-    pub image_id: String,
-    pub title: String,
 }
 
 #[derive(FromRow)]
 pub(crate) struct Transcript {
     pub id: i32,
-    pub created_at: NaiveDateTime,
-    pub remote_id: i32,
-}
-
-#[derive(FromRow)]
-pub(crate) struct TranscriptSummary {
-    pub id: i32,
-    pub created_at: NaiveDateTime,
-    pub transcript_id: i32,
 }
 
 #[derive(FromRow, Serialize)]
@@ -31,4 +18,6 @@ pub(crate) struct Summary {
     pub link: String,
     pub image_id: Option<String>,
     pub title: Option<String>,
+    pub channel_name: Option<String>,
+    pub duration_secs: Option<i64>,
 }
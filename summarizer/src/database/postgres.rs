@@ -1,6 +1,7 @@
-use super::models::{Remoteurl, Summary, Transcript, TranscriptSummary};
+use super::models::{Remoteurl, Summary, Transcript};
 use sqlx::postgres::PgPool;
 use sqlx::Error;
+use youtube_transcript::VideoMetadata;
 pub(crate) struct Postgresmethods<'a> {
     client: &'a PgPool,
 }
@@ -14,7 +15,7 @@ impl<'a> Postgresmethods<'a> {
 impl<'a> Postgresmethods<'a> {
     pub(crate) async fn get_summaries(&self) -> Result<Vec<Summary>, Error> {
         let query =
-            "SELECT ts.created_at, ts.content, remoteurl.link,remoteurl.image_id,remoteurl.title 
+            "SELECT ts.created_at, ts.content, remoteurl.link,remoteurl.image_id,remoteurl.title,remoteurl.channel_name,remoteurl.duration_secs 
         FROM transcriptsummary ts 
         JOIN transcript ON ts.transcript_id=transcript.id 
         JOIN remoteurl ON transcript.remote_id=remoteurl.id";
@@ -28,14 +29,22 @@ impl<'a> Postgresmethods<'a> {
         url: &str,
         image_id: &str,
         title: &str,
+        metadata: Option<&VideoMetadata>,
     ) -> Result<Remoteurl, Error> {
-        let insert_query = format!(
-            "INSERT INTO remoteurl (link,image_id,title) VALUES ('{}','{}','{}') RETURNING *",
-            url, image_id, title
-        );
-        sqlx::query_as::<_, Remoteurl>(&insert_query)
-            .fetch_one(self.client)
-            .await
+        // youtube controlled values are bound as parameters, columns of missing metadata are left NULL
+        sqlx::query_as::<_, Remoteurl>(
+            "INSERT INTO remoteurl (link,image_id,title,channel_name,channel_id,duration_secs,publish_date,view_count) VALUES ($1,$2,$3,$4,$5,$6,$7,$8) RETURNING id",
+        )
+        .bind(url)
+        .bind(image_id)
+        .bind(title)
+        .bind(metadata.map(|x| x.channel_name.as_str()))
+        .bind(metadata.map(|x| x.channel_id.as_str()))
+        .bind(metadata.map(|x| i64::try_from(x.duration.as_secs()).unwrap_or(i64::MAX)))
+        .bind(metadata.and_then(|x| x.publish_date.as_deref()))
+        .bind(metadata.map(|x| i64::try_from(x.view_count).unwrap_or(i64::MAX)))
+        .fetch_one(self.client)
+        .await
     }
     pub(crate) async fn insert_transcript(
         &self,
@@ -44,7 +53,7 @@ impl<'a> Postgresmethods<'a> {
     ) -> Result<Transcript, Error> {
         let transcript = transcript.replace("'", "''"); // Escape single quotes
         let insert_query = format!(
-            "INSERT INTO transcript (remote_id,content) VALUES ({},'{}') RETURNING id, created_at, remote_id",
+            "INSERT INTO transcript (remote_id,content) VALUES ({},'{}') RETURNING id",
             remote_url.id, transcript
         );
         sqlx::query_as::<_, Transcript>(&insert_query)
@@ -55,35 +64,27 @@ impl<'a> Postgresmethods<'a> {
         &self,
         summary: &str,
         transcript: &Transcript,
-    ) -> Result<TranscriptSummary, Error> {
+    ) -> Result<(), Error> {
         let summary = summary.replace("'", "''"); // Escape single quotes
         let insert_query = format!(
-            "INSERT INTO transcriptsummary (transcript_id,content) VALUES ({},'{}') RETURNING id, created_at, transcript_id",
+            "INSERT INTO transcriptsummary (transcript_id,content) VALUES ({},'{}')",
             transcript.id, summary
         );
-        sqlx::query_as::<_, TranscriptSummary>(&insert_query)
-            .fetch_one(self.client)
-            .await
+        sqlx::query(&insert_query).execute(self.client).await?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use chrono::NaiveDateTime;
     #[tokio::test]
     #[ignore = "Will activate after mocking db"]
     async fn insert_trans() {
         let p_url = "postgres://postgres:postgres@db/summarizer";
         let pool = PgPool::connect(p_url).await.unwrap();
         let pmethods = Postgresmethods::new(&pool);
-        let r_url = Remoteurl {
-            id: 1,
-            created_at: NaiveDateTime::MIN,
-            link: "sadf".to_string(),
-            image_id: "asfd".to_string(),
-            title: "asdf".to_string(),
-        };
+        let r_url = Remoteurl { id: 1 };
         pmethods
             .insert_transcript("asdfdsaf", &r_url)
             .await
@@ -1,2 +1,7 @@
 pub(crate) const GPT_MODEL: &str = "gpt-3.5-turbo";
 pub(crate) const SYSTEM_PROMPT: &str = "You will summarize the text, that can be readable in one to two minutes. Treat every input I type as a big text and help to summarize";
+pub(crate) const CHUNK_PROMPT: &str = "{text}";
+pub(crate) const REDUCE_PROMPT: &str =
+    "Combine the following partial summaries of a text into a single summary:\n\n{text}";
+pub(crate) const REFINE_PROMPT: &str = "Here is a summary of the beginning of a text:\n\n{summary}\n\nRefine the summary with the continuation of the text:\n\n{text}";
+pub(crate) const MAX_REDUCE_DEPTH: usize = 4;
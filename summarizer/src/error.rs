@@ -10,9 +10,11 @@ use sqlx::Error as Sqlxerror;
 use std::env::VarError;
 use std::io::Error as Ioerror;
 use std::{error::Error, fmt::Display};
+use youtube_transcript::Error as YoutubeError;
 #[derive(Debug, Serialize)]
 pub enum Serror {
     Youtubefetch(String),
+    YoutubeUnavailable(String),
     Scheduler(String),
     Database(String),
     Environment(String),
@@ -20,6 +22,7 @@ pub enum Serror {
     OpenAIError(String),
     Tokenize(String),
     Communication(String),
+    InvalidConfig(String),
 }
 
 impl IntoResponse for Serror {
@@ -32,6 +35,7 @@ impl Display for Serror {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Youtubefetch(x) => write!(f, "Youtubefetch: {}", x),
+            Self::YoutubeUnavailable(x) => write!(f, "YoutubeUnavailable: {}", x),
             Self::Scheduler(x) => write!(f, "Scheduler: {}", x),
             Self::Database(x) => write!(f, "Database: {}", x),
             Self::Environment(x) => write!(f, "Environment {}", x),
@@ -39,12 +43,20 @@ impl Display for Serror {
             Self::OpenAIError(x) => write!(f, "Openai: {}", x),
             Self::Tokenize(x) => writeln!(f, "Tokenize: {}", x),
             Self::Communication(x) => writeln!(f, "Request: {}", x),
+            Self::InvalidConfig(x) => write!(f, "InvalidConfig: {}", x),
         }
     }
 }
 
 impl Error for Serror {}
 
+impl Serror {
+    /// whether the job may succeed when retried later
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Self::YoutubeUnavailable(_) | Self::InvalidConfig(_))
+    }
+}
+
 impl From<Ioerror> for Serror {
     fn from(value: Ioerror) -> Self {
         Self::Scheduler(value.to_string())
@@ -92,3 +104,13 @@ impl From<regexError> for Serror {
         Self::Other(value.to_string())
     }
 }
+
+impl From<YoutubeError> for Serror {
+    fn from(value: YoutubeError) -> Self {
+        if value.is_retryable() {
+            Self::Youtubefetch(value.to_string())
+        } else {
+            Self::YoutubeUnavailable(value.to_string())
+        }
+    }
+}
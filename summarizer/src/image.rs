@@ -1,6 +0,0 @@
-use std::path::PathBuf;
-
-#[derive(Debug)]
-pub(crate) struct Image {
-    filepath: PathBuf,
-}
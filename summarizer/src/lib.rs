@@ -1,4 +1,6 @@
 pub mod api;
+pub mod backend;
+mod config;
 mod database;
 mod default;
 pub mod error;
@@ -7,6 +9,7 @@ mod summarize;
 mod tokenize;
 pub mod utils;
 mod youtube;
-pub use summarize::Summarizer;
+pub use config::{ConfigLimits, SummarizerConfig, SummarizerConfigBuilder};
+pub use summarize::{Summarizer, SummaryStrategy};
+pub use tokenize::{ChunkStrategy, Encoding, ModelSpec, ModelTokenizer, TokenizerRegistry};
 pub use youtube::{Youtube, YoutubeContent};
-mod image;
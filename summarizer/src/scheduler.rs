@@ -1,16 +1,59 @@
 use crate::database::Postgresmethods;
 use crate::error::Serror;
-use crate::youtube::Youtube;
-use crate::Summarizer;
+use crate::youtube::{text, Youtube};
+use crate::{Summarizer, SummarizerConfig};
 use apalis::layers::Extension;
 use apalis::postgres::PostgresStorage;
 use apalis::prelude::*;
 use reqwest;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgPool;
+use std::sync::Arc;
 use std::{fs, io::Write};
+use youtube_transcript::YoutubeBuilder;
 #[derive(Deserialize, Serialize)]
-pub struct Youtubelink(pub String);
+#[serde(from = "YoutubelinkJob")]
+pub struct Youtubelink {
+    pub link: String,
+    /// config of the summary, the worker's own config is used if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<SummarizerConfig>,
+}
+
+impl Youtubelink {
+    pub fn new(link: impl Into<String>) -> Self {
+        Self {
+            link: link.into(),
+            config: None,
+        }
+    }
+
+    pub fn with_config(mut self, config: SummarizerConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+}
+
+/// jobs queued before the config was added only hold the link.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum YoutubelinkJob {
+    Link(String),
+    Job {
+        link: String,
+        #[serde(default)]
+        config: Option<SummarizerConfig>,
+    },
+}
+
+impl From<YoutubelinkJob> for Youtubelink {
+    fn from(value: YoutubelinkJob) -> Self {
+        match value {
+            YoutubelinkJob::Link(link) => Self::new(link),
+            YoutubelinkJob::Job { link, config } => Self { link, config },
+        }
+    }
+}
 
 impl Job for Youtubelink {
     const NAME: &'static str = "youtube-transcript";
@@ -19,23 +62,49 @@ impl Job for Youtubelink {
 pub async fn transcript_summary(
     job: impl Into<Youtubelink>,
     ctx: JobContext,
-) -> Result<(), Serror> {
+) -> Result<JobResult, Serror> {
+    match summarize_link(job.into(), ctx).await {
+        Ok(()) => Ok(JobResult::Success),
+        // retrying cannot help when the video is private, has no captions etc.
+        Err(err) if !err.is_retryable() => {
+            log::warn!("Killing the job: {}", err);
+            Ok(JobResult::Kill)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+async fn summarize_link(youtube_link: Youtubelink, ctx: JobContext) -> Result<(), Serror> {
     let pgpool = ctx
         .data_opt::<PgPool>()
         .ok_or_else(|| Serror::Other("Cannot observe Pgpool connection".to_string()))?;
     let summarizer = ctx
         .data_opt::<Summarizer>()
         .ok_or(Serror::Other("Summarizer cannot be found".to_string()))?;
-    let pm = Postgresmethods::new(&pgpool);
-    let youtube_link: Youtubelink = job.into();
-    let youtube_content = Youtube::link(&youtube_link.0)?.content().await?;
+    let config = match youtube_link.config.as_ref() {
+        Some(config) => {
+            config.validate(summarizer.registry())?;
+            config
+        }
+        None => summarizer.config(),
+    };
+    let loader = ctx
+        .data_opt::<Arc<youtube_transcript::Youtube>>()
+        .ok_or(Serror::Other("Youtube loader cannot be found".to_string()))?;
+    let pm = Postgresmethods::new(pgpool);
+    let youtube_content = Youtube::link_with(&youtube_link.link, loader)?
+        .content()
+        .await?;
 
     // description
-    let description = youtube_content.transcript_text().await?;
+    let transcript = youtube_content.transcirpt().await?;
+    let description = text(&transcript.transcripts);
 
-    // title
-    let title = youtube_content
-        .title()?
+    // metadata
+    let metadata = youtube_content.metadata().ok();
+    let title = metadata
+        .as_ref()
+        .map(|x| x.title.clone())
         .unwrap_or("[Title Not Found]".to_string());
 
     // image
@@ -51,26 +120,49 @@ pub async fn transcript_summary(
     file.write_all(&image_raw)?;
 
     let remote_url = pm
-        .insert_remoteurl(&youtube_link.0, &youtube_content.video_id, &title)
+        .insert_remoteurl(
+            &youtube_link.link,
+            &youtube_content.video_id,
+            &title,
+            metadata.as_ref(),
+        )
         .await?;
 
     let ts = pm.insert_transcript(&description, &remote_url).await?;
-    let _summary = summarizer.summarize(&description).await?;
+    // one summary section per chapter, if the video has chapters
+    let chapters = youtube_content.chapters();
+    let _summary = if chapters.is_empty() {
+        summarizer.summarize_with(&description, config).await?
+    } else {
+        let mut sections = vec![];
+        for chapter in transcript.by_chapters(&chapters) {
+            let chapter_text = text(&chapter.transcripts);
+            if chapter_text.trim().is_empty() {
+                continue;
+            }
+            let summary = summarizer.summarize_with(&chapter_text, config).await?;
+            sections.push(format!("## {}\n{}", chapter.chapter.title, summary));
+        }
+        sections.join("\n\n")
+    };
     pm.insert_transcriptsummary(&_summary, &ts).await?;
     Ok(())
 }
 
 pub async fn setup_youtube_data_workers(postgres_url: &str) -> Result<(), Serror> {
-    let pgpool = PgPool::connect(&postgres_url).await?;
+    let pgpool = PgPool::connect(postgres_url).await?;
     let ps_client = PostgresStorage::<Youtubelink>::new(pgpool.clone());
     ps_client.setup().await?;
     let summarizer = Summarizer::default_params()?;
+    // every job fetches youtube with the same http client
+    let loader = Arc::new(YoutubeBuilder::default().try_build()?);
     ps_client.setup().await?;
     Monitor::new()
         .register_with_count(1, move |_| {
             WorkerBuilder::new(ps_client.clone())
                 .layer(Extension(pgpool.clone()))
                 .layer(Extension(summarizer.clone()))
+                .layer(Extension(loader.clone()))
                 .build_fn(transcript_summary)
         })
         .run()
@@ -81,6 +173,19 @@ pub async fn setup_youtube_data_workers(postgres_url: &str) -> Result<(), Serror
 #[cfg(test)]
 mod test {
     use super::*;
+
+    #[test]
+    fn test_youtubelink_job() {
+        let job: Youtubelink = serde_json::from_str(r#""https://youtu.be/GJLlxj_dtq8""#).unwrap();
+        assert_eq!(job.link, "https://youtu.be/GJLlxj_dtq8");
+        assert!(job.config.is_none());
+        let config = SummarizerConfig::builder().model("llama3").build();
+        let job = Youtubelink::new("GJLlxj_dtq8").with_config(config.clone());
+        let job: Youtubelink = serde_json::from_str(&serde_json::to_string(&job).unwrap()).unwrap();
+        assert_eq!(job.config, Some(config));
+        let job: Youtubelink = serde_json::from_str(r#"{"link":"GJLlxj_dtq8"}"#).unwrap();
+        assert!(job.config.is_none());
+    }
     #[test]
     fn test_write_file() {
         let path = "./data";
@@ -90,6 +195,6 @@ mod test {
             .create(true)
             .open(format!("{}/{}.jpg", path, "asdf"))
             .unwrap();
-        file.write_all(&vec![12, 23, 45]).unwrap();
+        file.write_all(&[12, 23, 45]).unwrap();
     }
 }
@@ -1,12 +1,11 @@
-use crate::default;
+use crate::backend::{self, ChatBackend, ChatMessage, ChatRequest};
+use crate::config::SummarizerConfig;
 use crate::error::Serror;
-use crate::tokenize::{OpenAI, Tokenizer};
-use async_openai::{
-    types::{ChatCompletionRequestMessageArgs, CreateChatCompletionRequestArgs, Role},
-    Client,
-};
-use futures::future::join_all;
-use log;
+use crate::tokenize::{Chunking, ModelTokenizer, Tokenizer, TokenizerRegistry};
+use futures::future::{try_join_all, BoxFuture};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
 
 pub trait Summarize {
     fn description(&self) -> &str;
@@ -14,109 +13,295 @@ pub trait Summarize {
 
 impl Summarize for String {
     fn description(&self) -> &str {
-        &self
+        self
+    }
+}
+
+/// How the summaries of the chunks make up the summary of the content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryStrategy {
+    /// summarizes the chunks in parallel, then merges groups of summaries with the reduce prompt,
+    /// pass after pass, until a single summary remains.
+    #[default]
+    MapReduce,
+    /// summarizes the first chunk, then refines the summary with each chunk in order with the refine prompt.
+    /// Slower, but keeps the flow of narrative content like lectures.
+    Refine,
+    /// summarizes the whole content in a single request, for models with a long context window.
+    SingleShot,
+}
+
+impl FromStr for SummaryStrategy {
+    type Err = Serror;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "map_reduce" => Ok(Self::MapReduce),
+            "refine" => Ok(Self::Refine),
+            "single_shot" => Ok(Self::SingleShot),
+            _ => Err(Serror::InvalidConfig(format!(
+                "unknown summary strategy: {}",
+                s
+            ))),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct Summarizer {
-    openai: OpenAI,
-    client: Client,
+    registry: TokenizerRegistry,
+    backend: Arc<dyn ChatBackend>,
+    config: SummarizerConfig,
 }
 
 impl Summarizer {
     pub fn default_params() -> Result<Self, Serror> {
         log::info!("Initializing Summarizer from default params");
         Ok(Self {
-            openai: OpenAI::default_params()?,
-            client: Client::new(),
+            registry: TokenizerRegistry::default(),
+            backend: backend::from_env(),
+            config: SummarizerConfig::from_env()?,
         })
     }
+
+    pub fn with_backend(backend: impl ChatBackend + 'static) -> Self {
+        Self {
+            registry: TokenizerRegistry::default(),
+            backend: Arc::new(backend),
+            config: SummarizerConfig::default(),
+        }
+    }
+
+    /// set the config used by [`Summarizer::summarize`]
+    pub fn with_config(mut self, config: SummarizerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// set the registry giving the encoding and the token limits of the models, see [`TokenizerRegistry::register`]
+    pub fn with_registry(mut self, registry: TokenizerRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    pub fn registry(&self) -> &TokenizerRegistry {
+        &self.registry
+    }
+
+    pub fn config(&self) -> &SummarizerConfig {
+        &self.config
+    }
 }
 
 impl Summarizer {
     pub async fn summarize(&self, x: &impl Summarize) -> Result<String, Serror> {
-        log::info!("Summarizing the Content");
-        let client = &self.client;
-        let chat = client.chat();
-        let mut interm: String;
-        let mut content = x.description();
+        self.summarize_with(x, &self.config).await
+    }
+
+    /// summarizes with the config instead of the summarizer's own, ex: a config sent along a job.
+    pub async fn summarize_with(
+        &self,
+        x: &impl Summarize,
+        config: &SummarizerConfig,
+    ) -> Result<String, Serror> {
+        log::info!(
+            "Summarizing the Content with {} using {:?}",
+            config.model,
+            config.strategy
+        );
+        let content = x.description();
         log::debug!("Length of content: {}", content.len());
-        loop {
+        let tokenizer = self.registry.tokenizer(&config.model)?;
+        match config.strategy {
+            SummaryStrategy::MapReduce => self.map_reduce(&tokenizer, content, config).await,
+            SummaryStrategy::Refine => self.refine(&tokenizer, content, config).await,
+            SummaryStrategy::SingleShot => self.single_shot(&tokenizer, content, config).await,
+        }
+    }
+
+    /// summarizes the chunks in parallel, then merges groups of summaries until a single one remains.
+    async fn map_reduce(
+        &self,
+        tokenizer: &ModelTokenizer,
+        content: &str,
+        config: &SummarizerConfig,
+    ) -> Result<String, Serror> {
+        let max_tokens = config.content_chunk_size(tokenizer)?;
+        let chunking =
+            Chunking::new(config.chunk_strategy, max_tokens).overlap(config.chunk_overlap);
+        log::debug!("Breaking the content into segments with {:?}", chunking);
+        let segments = tokenizer.chunk(content, &chunking)?;
+        log::debug!("Got {} segments, summarizing individually", segments.len());
+        let mut summaries = try_join_all(
+            segments
+                .iter()
+                .map(|x| self.request(config, config.chunk_message(x))),
+        )
+        .await?;
+        // every pass merges groups of whole summaries fitting a request
+        let max_tokens = config.chunk_size(tokenizer, &config.reduce_message(""))?;
+        let mut depth = 0;
+        while summaries.len() > 1 {
+            if depth == config.max_reduce_depth {
+                return Err(Serror::Other(format!(
+                    "{} summaries are left after {} reduce passes",
+                    summaries.len(),
+                    depth
+                )));
+            }
+            let groups = group(tokenizer, &summaries, max_tokens)?;
             log::debug!(
-                "Breaking the content into segments with a max token limit of {}",
-                OpenAI::MAX_N_TOKENS
+                "Merging {} summaries in {} groups",
+                summaries.len(),
+                groups.len()
             );
-            let segments = self
-                .openai
-                .tokenize_in_max_tokenlimit(content)?
-                .detokenize_inarray()?;
-            log::debug!("Got {} segments", segments.len());
-
-            log::debug!("Creating Chat request for each segment");
-            let all_requests: Vec<Result<_, Serror>> = segments
-                .iter()
-                .map(|x| {
-                    let request = CreateChatCompletionRequestArgs::default()
-                        .model(default::GPT_MODEL)
-                        .messages([
-                            ChatCompletionRequestMessageArgs::default()
-                                .role(Role::System)
-                                .content(default::SYSTEM_PROMPT)
-                                .build()?,
-                            ChatCompletionRequestMessageArgs::default()
-                                .role(Role::User)
-                                .content(x.description())
-                                .build()?,
-                        ])
-                        .build()?;
-                    Ok(chat.create(request))
-                })
-                .collect::<Vec<_>>();
-            if all_requests.len() == 1 {
-                log::debug!("Got only one segment!, summarizing for that content");
-                return Ok(all_requests
-                    .into_iter()
-                    .next()
-                    .ok_or(Serror::Other("cannot find first request".to_string()))??
-                    .await?
-                    .choices
-                    .into_iter()
-                    .next()
-                    .ok_or(Serror::Other("cannot find summary".to_string()))?
-                    .message
-                    .content);
-            } else {
-                log::debug!(
-                    "For the {} segments, summarizing individually and finally merging them to a single string.",
-                    all_requests.len()
-                );
-                let futures = all_requests.into_iter().filter_map(|x| x.ok());
-                interm = join_all(futures)
-                    .await
-                    .into_iter()
-                    .filter_map(|x| {
-                        let x = x.unwrap().choices.into_iter().next()?.message.content;
-                        Some(x)
-                    })
-                    .collect::<String>();
-                content = &interm;
-                log::debug!("Looping again with the content to be this summarized content")
+            summaries = try_join_all(
+                groups
+                    .iter()
+                    .map(|x| self.request(config, config.reduce_message(x))),
+            )
+            .await?;
+            depth += 1;
+        }
+        summaries
+            .pop()
+            .ok_or(Serror::Other("cannot find any segment".to_string()))
+    }
+
+    /// summarizes the first chunk, then updates the summary with each following chunk in order.
+    async fn refine(
+        &self,
+        tokenizer: &ModelTokenizer,
+        content: &str,
+        config: &SummarizerConfig,
+    ) -> Result<String, Serror> {
+        let max_tokens = config.content_chunk_size(tokenizer)?;
+        let chunking =
+            Chunking::new(config.chunk_strategy, max_tokens).overlap(config.chunk_overlap);
+        log::debug!("Breaking the content into segments with {:?}", chunking);
+        let segments = tokenizer.chunk(content, &chunking)?;
+        log::debug!(
+            "Got {} segments, refining the summary with each",
+            segments.len()
+        );
+        let mut segments = segments.iter();
+        let first = segments
+            .next()
+            .ok_or(Serror::Other("cannot find any segment".to_string()))?;
+        let mut summary = self.request(config, config.chunk_message(first)).await?;
+        for segment in segments {
+            summary = self
+                .request(config, config.refine_message(&summary, segment))
+                .await?;
+        }
+        Ok(summary)
+    }
+
+    /// summarizes the whole content in one request, failing when it doesn't fit the context window.
+    async fn single_shot(
+        &self,
+        tokenizer: &ModelTokenizer,
+        content: &str,
+        config: &SummarizerConfig,
+    ) -> Result<String, Serror> {
+        let max_tokens = config.content_chunk_size(tokenizer)?;
+        let n_tokens = tokenizer.count_tokens(content);
+        if n_tokens > max_tokens {
+            return Err(Serror::Tokenize(format!(
+                "the content has {} tokens, {} fits in a single request to {}",
+                n_tokens, max_tokens, config.model
+            )));
+        }
+        self.request(config, config.chunk_message(content.trim()))
+            .await
+    }
+
+    fn request(
+        &self,
+        config: &SummarizerConfig,
+        message: String,
+    ) -> BoxFuture<'_, Result<String, Serror>> {
+        self.backend.chat(ChatRequest {
+            model: config.model.clone(),
+            messages: vec![
+                ChatMessage::system(&config.system_prompt),
+                ChatMessage::user(message),
+            ],
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+        })
+    }
+}
+
+/// separator of the summaries merged in a request.
+const SUMMARY_SEPARATOR: &str = "\n\n";
+
+/// packs whole summaries into groups of at most `max_tokens` tokens, in order.
+/// A summary longer than the budget is split so that every group still fits a request.
+fn group(
+    tokenizer: &ModelTokenizer,
+    summaries: &[String],
+    max_tokens: usize,
+) -> Result<Vec<String>, Serror> {
+    let separator = tokenizer.count_tokens(SUMMARY_SEPARATOR);
+    let mut groups = vec![];
+    let mut current: Vec<String> = vec![];
+    let mut n_tokens = 0;
+    for summary in summaries {
+        let summary = summary.trim();
+        let pieces = if tokenizer.count_tokens(summary) + separator > max_tokens {
+            let size = max_tokens.saturating_sub(separator).max(1);
+            log::debug!("Splitting a summary longer than {} tokens", size);
+            tokenizer.split_tokens(summary, size, 0)?
+        } else {
+            vec![summary.to_string()]
+        };
+        for piece in pieces {
+            let tokens = tokenizer.count_tokens(&piece) + separator;
+            if n_tokens + tokens > max_tokens && !current.is_empty() {
+                groups.push(current.join(SUMMARY_SEPARATOR));
+                current.clear();
+                n_tokens = 0;
             }
+            n_tokens += tokens;
+            current.push(piece);
         }
     }
+    if !current.is_empty() {
+        groups.push(current.join(SUMMARY_SEPARATOR));
+    }
+    Ok(groups)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::backend::FakeChat;
+    use crate::tokenize::{Encoding, ModelSpec};
     use crate::Youtube;
-    use env_logger;
+    use std::sync::Mutex;
+
+    /// replies with a summary made of two paragraphs, keeping the messages it was sent.
+    #[derive(Default)]
+    struct ListChat {
+        messages: Mutex<Vec<String>>,
+    }
+
+    impl ChatBackend for ListChat {
+        fn chat(&self, request: ChatRequest) -> BoxFuture<'_, Result<String, Serror>> {
+            if let Some(message) = request.messages.last() {
+                self.messages.lock().unwrap().push(message.content.clone());
+            }
+            let points = "point ".repeat(300);
+            Box::pin(async move { Ok(format!("- first {points}\n\n- second {points}")) })
+        }
+    }
     fn log_init() {
         env_logger::builder().is_test(true).try_init().unwrap();
     }
-    struct DUMMY;
-    impl Summarize for DUMMY {
+    struct Dummy;
+    impl Summarize for Dummy {
         fn description(&self) -> &str {
             "The thread initiating the shutdown blocks until all spawned work has been stopped. This can take an indefinite amount of time. The Drop implementation waits forever for this.
             
@@ -126,12 +311,124 @@ mod test {
         }
     }
     #[tokio::test]
-    #[ignore = "Requires mocking openai response"]
     async fn summarize() {
-        log_init();
-        let summarizer = Summarizer::default_params().unwrap();
-        let resp = summarizer.summarize(&DUMMY).await.unwrap();
-        println!("{resp}")
+        let fake = FakeChat::new(4);
+        let summarizer = Summarizer::with_backend(fake.clone());
+        let resp = summarizer.summarize(&Dummy).await.unwrap();
+        assert_eq!(resp, "The thread initiating the");
+        assert_eq!(fake.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn summarize_segments() {
+        // every segment is summarized to its first words, looping until a single segment remains
+        let fake = FakeChat::new(2);
+        let registry = TokenizerRegistry::default().register(
+            "gpt-3.5-turbo",
+            ModelSpec::new(Encoding::Cl100kBase, 4_096, 1_024),
+        );
+        let summarizer = Summarizer::with_backend(fake.clone()).with_registry(registry);
+        let content = "word ".repeat(5000);
+        let resp = summarizer.summarize(&content).await.unwrap();
+        // the last pass merges the summaries with the reduce prompt
+        assert_eq!(resp, "Combine the");
+        assert_eq!(fake.requests(), 3);
+        // the default context window of the model fits the content in a single request
+        let fake = FakeChat::new(2);
+        let summarizer = Summarizer::with_backend(fake.clone());
+        assert_eq!(summarizer.summarize(&content).await.unwrap(), "word word");
+        assert_eq!(fake.requests(), 1);
+        let config = SummarizerConfig::builder()
+            .chunk_prompt("Chunk: {text}")
+            .build();
+        let resp = summarizer.summarize_with(&Dummy, &config).await.unwrap();
+        assert_eq!(resp, "Chunk: The");
+    }
+
+    #[tokio::test]
+    async fn summarize_reduce_groups() {
+        // summaries with blank lines are merged whole, never split across groups
+        let registry = TokenizerRegistry::default().register(
+            "gpt-3.5-turbo",
+            ModelSpec::new(Encoding::Cl100kBase, 4_096, 1_024),
+        );
+        let chat = Arc::new(ListChat::default());
+        let summarizer = Summarizer {
+            registry,
+            backend: chat.clone(),
+            config: SummarizerConfig::default(),
+        };
+        let content = "word ".repeat(20_000);
+        summarizer.summarize(&content).await.unwrap();
+        let messages = chat.messages.lock().unwrap();
+        let reduces = messages
+            .iter()
+            .filter(|x| x.starts_with("Combine"))
+            .collect::<Vec<_>>();
+        assert!(reduces.len() > 2, "{}", reduces.len());
+        for message in reduces {
+            assert!(message.contains("- first"));
+            assert_eq!(
+                message.matches("- first").count(),
+                message.matches("- second").count()
+            );
+        }
+    }
+
+    #[test]
+    fn group_splits_long_summaries() {
+        let registry = TokenizerRegistry::default();
+        let tokenizer = registry.tokenizer("gpt-4o").unwrap();
+        let summaries = [
+            "short".to_string(),
+            "word ".repeat(100),
+            "short".to_string(),
+        ];
+        let groups = group(&tokenizer, &summaries, 30).unwrap();
+        assert!(groups.len() > 3, "{}", groups.len());
+        for x in &groups {
+            assert!(tokenizer.count_tokens(x) <= 30, "{}", x);
+        }
+        assert_eq!(groups[0], "short");
+        assert!(groups[1].starts_with("word"));
+        assert!(groups.last().unwrap().ends_with("\n\nshort"));
+    }
+
+    #[tokio::test]
+    async fn summarize_strategies() {
+        let registry = TokenizerRegistry::default().register(
+            "gpt-3.5-turbo",
+            ModelSpec::new(Encoding::Cl100kBase, 4_096, 1_024),
+        );
+        let content = "word ".repeat(5000);
+        // the running summary is refined with every following chunk
+        let fake = FakeChat::new(2);
+        let summarizer = Summarizer::with_backend(fake.clone()).with_registry(registry.clone());
+        let config = SummarizerConfig::builder()
+            .strategy(SummaryStrategy::Refine)
+            .build();
+        let resp = summarizer.summarize_with(&content, &config).await.unwrap();
+        assert_eq!(resp, "Here is");
+        assert_eq!(fake.requests(), 3);
+        // the merging passes are bounded
+        let config = SummarizerConfig::builder().max_reduce_depth(0).build();
+        assert!(summarizer.summarize_with(&content, &config).await.is_err());
+        // a single request only if the content fits
+        let config = SummarizerConfig::builder()
+            .strategy(SummaryStrategy::SingleShot)
+            .build();
+        assert!(summarizer.summarize_with(&content, &config).await.is_err());
+        let summarizer = Summarizer::with_backend(fake.clone());
+        let resp = summarizer.summarize_with(&content, &config).await.unwrap();
+        assert_eq!(resp, "word word");
+        assert_eq!(
+            "single_shot".parse::<SummaryStrategy>().unwrap(),
+            SummaryStrategy::SingleShot
+        );
+        assert!(matches!(
+            "one_shot".parse::<SummaryStrategy>(),
+            Err(Serror::InvalidConfig(_))
+        ));
     }
 
     #[tokio::test]
@@ -0,0 +1,291 @@
+use super::token::Tokenizer;
+use crate::error::Serror;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// characters ending a sentence when followed by whitespace or the end of the text.
+const SENTENCE_TERMINATORS: [char; 6] = ['.', '!', '?', '。', '！', '？'];
+
+/// How a text is cut into chunks fitting a token budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkStrategy {
+    /// cuts the token stream every `max_tokens` tokens, regardless of words or characters.
+    Fixed,
+    /// packs whole sentences, splitting sentences longer than the budget at words.
+    #[default]
+    Sentences,
+    /// packs whole paragraphs, splitting paragraphs longer than the budget at sentences.
+    Paragraphs,
+}
+
+impl FromStr for ChunkStrategy {
+    type Err = Serror;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "sentences" => Ok(Self::Sentences),
+            "paragraphs" => Ok(Self::Paragraphs),
+            _ => Err(Serror::InvalidConfig(format!(
+                "unknown chunk strategy: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Strategy and token budget of the chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunking {
+    pub strategy: ChunkStrategy,
+    pub max_tokens: usize,
+    /// tokens of the end of a chunk repeated at the start of the next one, always less than `max_tokens`.
+    pub overlap: usize,
+}
+
+impl Chunking {
+    pub fn new(strategy: ChunkStrategy, max_tokens: usize) -> Self {
+        Self {
+            strategy,
+            max_tokens: max_tokens.max(1),
+            overlap: 0,
+        }
+    }
+
+    pub fn overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap.min(self.max_tokens - 1);
+        self
+    }
+
+    pub(crate) fn chunk(
+        &self,
+        tokenizer: &(impl Tokenizer + ?Sized),
+        text: &str,
+    ) -> Result<Vec<String>, Serror> {
+        match self.strategy {
+            ChunkStrategy::Fixed => tokenizer.split_tokens(text, self.max_tokens, self.overlap),
+            ChunkStrategy::Sentences => Ok(self.pack(tokenizer, split_sentences(text))),
+            ChunkStrategy::Paragraphs => Ok(self.pack(tokenizer, split_paragraphs(text))),
+        }
+    }
+
+    /// greedily packs the units into chunks, splitting the units that don't fit in a chunk on their own.
+    fn pack(&self, tokenizer: &(impl Tokenizer + ?Sized), units: Vec<&str>) -> Vec<String> {
+        let mut pieces = vec![];
+        for unit in units {
+            self.fit(tokenizer, unit, &mut pieces);
+        }
+        let join = |pieces: &[(&str, usize)]| {
+            pieces
+                .iter()
+                .map(|(x, _)| *x)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        };
+        let mut chunks = vec![];
+        let mut current: Vec<(&str, usize)> = vec![];
+        let mut n_tokens = 0;
+        for piece in pieces {
+            if n_tokens + piece.1 > self.max_tokens && !current.is_empty() {
+                chunks.push(join(&current));
+                // whole pieces from the end of the chunk are carried over, within the overlap and the budget
+                let mut overlap = 0;
+                let kept = current
+                    .iter()
+                    .rev()
+                    .take_while(|x| {
+                        overlap += x.1;
+                        overlap <= self.overlap && overlap + piece.1 <= self.max_tokens
+                    })
+                    .count();
+                current.drain(..current.len() - kept);
+                n_tokens = current.iter().map(|x| x.1).sum();
+            }
+            n_tokens += piece.1;
+            current.push(piece);
+        }
+        if !current.is_empty() {
+            chunks.push(join(&current));
+        }
+        chunks.retain(|x| !x.is_empty());
+        chunks
+    }
+
+    /// pushes the unit with its token count, split at sentences, words and characters until the pieces fit.
+    fn fit<'a>(
+        &self,
+        tokenizer: &(impl Tokenizer + ?Sized),
+        unit: &'a str,
+        pieces: &mut Vec<(&'a str, usize)>,
+    ) {
+        let n_tokens = tokenizer.count_tokens(unit);
+        if n_tokens <= self.max_tokens {
+            pieces.push((unit, n_tokens));
+            return;
+        }
+        let mut parts = split_sentences(unit);
+        if parts.len() <= 1 {
+            parts = split_words(unit);
+        }
+        if parts.len() <= 1 {
+            parts = unit
+                .char_indices()
+                .map(|(i, x)| &unit[i..i + x.len_utf8()])
+                .collect();
+        }
+        if parts.len() <= 1 {
+            // a single character longer than the budget
+            pieces.push((unit, n_tokens));
+            return;
+        }
+        for part in parts {
+            self.fit(tokenizer, part, pieces);
+        }
+    }
+}
+
+/// splits the text at the offsets where `is_boundary` holds, the pieces concatenate back to the text.
+/// Pieces start with the whitespace separating them from the previous one, as the bpe tokens do.
+fn split_at(text: &str, is_boundary: impl Fn(&str, usize) -> bool) -> Vec<&str> {
+    let mut pieces = vec![];
+    let mut start = 0;
+    for (i, _) in text.char_indices() {
+        if i > start && is_boundary(text, i) {
+            pieces.push(&text[start..i]);
+            start = i;
+        }
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+/// whitespace starting a piece at the offset: after a non whitespace character, up to the next word.
+fn whitespace_run(text: &str, i: usize) -> Option<&str> {
+    let prev = text[..i].chars().next_back()?;
+    if prev.is_whitespace() || !text[i..].starts_with(char::is_whitespace) {
+        return None;
+    }
+    let end = text[i..]
+        .find(|x: char| !x.is_whitespace())
+        .map_or(text.len(), |x| i + x);
+    Some(&text[i..end])
+}
+
+/// sentences ending at a terminator followed by whitespace.
+fn split_sentences(text: &str) -> Vec<&str> {
+    split_at(text, |text, i| {
+        text[..i]
+            .chars()
+            .next_back()
+            .is_some_and(|x| SENTENCE_TERMINATORS.contains(&x))
+            && whitespace_run(text, i).is_some()
+    })
+}
+
+/// paragraphs separated by blank lines.
+fn split_paragraphs(text: &str) -> Vec<&str> {
+    split_at(text, |text, i| {
+        whitespace_run(text, i).is_some_and(|x| x.matches('\n').count() > 1)
+    })
+}
+
+/// words separated by whitespace.
+fn split_words(text: &str) -> Vec<&str> {
+    split_at(text, |text, i| whitespace_run(text, i).is_some())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenize::TokenizerRegistry;
+
+    #[test]
+    fn test_split() {
+        let text = "First one. Second one!  Third\n\nNew paragraph. 3.5 percent";
+        assert_eq!(
+            split_sentences(text),
+            [
+                "First one.",
+                " Second one!",
+                "  Third\n\nNew paragraph.",
+                " 3.5 percent"
+            ]
+        );
+        assert_eq!(
+            split_paragraphs(text),
+            [
+                "First one. Second one!  Third",
+                "\n\nNew paragraph. 3.5 percent"
+            ]
+        );
+        assert_eq!(split_words(" a  b"), [" a", "  b"]);
+    }
+
+    #[test]
+    fn test_sentence_chunks() {
+        let tokenizer = TokenizerRegistry::default().tokenizer("gpt-4").unwrap();
+        let text = "The cat sat. The dog ran. The bird flew. The fish swam.";
+        let chunks = Chunking::new(ChunkStrategy::Sentences, 10)
+            .chunk(&tokenizer, text)
+            .unwrap();
+        assert_eq!(
+            chunks,
+            ["The cat sat. The dog ran.", "The bird flew. The fish swam."]
+        );
+        let chunks = Chunking::new(ChunkStrategy::Sentences, 10)
+            .overlap(5)
+            .chunk(&tokenizer, text)
+            .unwrap();
+        assert_eq!(
+            chunks,
+            [
+                "The cat sat. The dog ran.",
+                "The dog ran. The bird flew.",
+                "The bird flew. The fish swam."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_oversized_sentence_chunks() {
+        let tokenizer = TokenizerRegistry::default().tokenizer("gpt-4").unwrap();
+        // multi-byte characters are never split across chunks
+        let text = "héllo wörld ünïcode tëxt wïthout any pünctuation ".repeat(3);
+        let chunks = Chunking::new(ChunkStrategy::Sentences, 12)
+            .chunk(&tokenizer, &text)
+            .unwrap();
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(tokenizer.count_tokens(chunk) <= 12, "{chunk}");
+        }
+        assert_eq!(chunks.join(" "), text.trim());
+    }
+
+    #[test]
+    fn test_fixed_chunks() {
+        let tokenizer = TokenizerRegistry::default().tokenizer("gpt-4").unwrap();
+        let text = "aurwfhdhbasdfiawifujbads";
+        let chunks = Chunking::new(ChunkStrategy::Fixed, 3)
+            .chunk(&tokenizer, text)
+            .unwrap();
+        assert_eq!(chunks.concat(), text);
+        assert_eq!(chunks.len(), 4);
+        let chunks = Chunking::new(ChunkStrategy::Fixed, 3)
+            .overlap(1)
+            .chunk(&tokenizer, text)
+            .unwrap();
+        assert_eq!(chunks.len(), 6);
+        assert_eq!(
+            "paragraphs".parse::<ChunkStrategy>().unwrap(),
+            ChunkStrategy::Paragraphs
+        );
+        assert!(matches!(
+            "words".parse::<ChunkStrategy>(),
+            Err(Serror::InvalidConfig(_))
+        ));
+    }
+}
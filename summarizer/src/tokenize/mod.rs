@@ -1,4 +1,6 @@
+mod chunk;
+mod registry;
 mod token;
-mod tokenizer;
+pub use chunk::{ChunkStrategy, Chunking};
+pub use registry::{Encoding, ModelSpec, ModelTokenizer, TokenizerRegistry};
 pub use token::Tokenizer;
-pub use tokenizer::OpenAI;
@@ -0,0 +1,288 @@
+use super::token::Tokenizer;
+use crate::error::Serror;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tiktoken_rs::{cl100k_base, o200k_base, CoreBPE};
+
+/// tokens kept free besides the prompt and the output, the chat format adds a few tokens per message.
+const MESSAGE_OVERHEAD: usize = 16;
+
+/// How the tokens of a model are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// bpe of gpt-4o and the o-series models.
+    O200kBase,
+    /// bpe of gpt-4 and gpt-3.5-turbo.
+    Cl100kBase,
+    /// estimated from the characters, for local models whose bpe is unknown.
+    Chars,
+}
+
+/// Encoding and token limits of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelSpec {
+    pub encoding: Encoding,
+    /// tokens of the prompt and the output together.
+    pub context_window: usize,
+    /// tokens reserved for the output when the request doesn't set `max_tokens`.
+    pub output_tokens: usize,
+}
+
+impl ModelSpec {
+    pub fn new(encoding: Encoding, context_window: usize, output_tokens: usize) -> Self {
+        Self {
+            encoding,
+            context_window,
+            output_tokens,
+        }
+    }
+}
+
+/// Specs of the models by name, a model takes the spec of the longest registered prefix of its name.
+/// Ex: "gpt-4o-2024-08-06" takes the spec of "gpt-4o"
+#[derive(Clone)]
+pub struct TokenizerRegistry {
+    models: Vec<(String, ModelSpec)>,
+    /// spec of the models matching no prefix.
+    fallback: ModelSpec,
+    /// bpes are loaded once and shared by every tokenizer.
+    bpes: Arc<Mutex<HashMap<Encoding, Arc<CoreBPE>>>>,
+    /// unknown models already warned about, a job resolves its model several times.
+    warned: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Default for TokenizerRegistry {
+    fn default() -> Self {
+        let models = [
+            (
+                "gpt-3.5-turbo",
+                ModelSpec::new(Encoding::Cl100kBase, 16_385, 1_024),
+            ),
+            (
+                "gpt-3.5-turbo-instruct",
+                ModelSpec::new(Encoding::Cl100kBase, 4_096, 1_024),
+            ),
+            ("gpt-4", ModelSpec::new(Encoding::Cl100kBase, 8_192, 1_024)),
+            (
+                "gpt-4-32k",
+                ModelSpec::new(Encoding::Cl100kBase, 32_768, 1_024),
+            ),
+            (
+                "gpt-4-turbo",
+                ModelSpec::new(Encoding::Cl100kBase, 128_000, 4_096),
+            ),
+            (
+                "gpt-4o",
+                ModelSpec::new(Encoding::O200kBase, 128_000, 4_096),
+            ),
+            (
+                "chatgpt-4o",
+                ModelSpec::new(Encoding::O200kBase, 128_000, 4_096),
+            ),
+            (
+                "gpt-4.1",
+                ModelSpec::new(Encoding::O200kBase, 1_047_576, 4_096),
+            ),
+            (
+                "gpt-4.5",
+                ModelSpec::new(Encoding::O200kBase, 128_000, 4_096),
+            ),
+            ("gpt-5", ModelSpec::new(Encoding::O200kBase, 400_000, 8_192)),
+            ("o1", ModelSpec::new(Encoding::O200kBase, 200_000, 8_192)),
+            (
+                "o1-mini",
+                ModelSpec::new(Encoding::O200kBase, 128_000, 8_192),
+            ),
+            (
+                "o1-preview",
+                ModelSpec::new(Encoding::O200kBase, 128_000, 8_192),
+            ),
+            ("o3", ModelSpec::new(Encoding::O200kBase, 200_000, 8_192)),
+            (
+                "o4-mini",
+                ModelSpec::new(Encoding::O200kBase, 200_000, 8_192),
+            ),
+        ];
+        Self {
+            models: models
+                .into_iter()
+                .map(|(name, spec)| (name.to_string(), spec))
+                .collect(),
+            fallback: ModelSpec::new(Encoding::Chars, 4_096, 512),
+            bpes: Arc::new(Mutex::new(HashMap::new())),
+            warned: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+}
+
+impl TokenizerRegistry {
+    /// set the spec of the models starting with the name, replacing any spec registered for the same name.
+    pub fn register(mut self, name: impl Into<String>, spec: ModelSpec) -> Self {
+        let name = name.into();
+        self.models.retain(|(x, _)| *x != name);
+        self.models.push((name, spec));
+        self
+    }
+
+    /// set the spec of the models matching no registered name.
+    pub fn fallback(mut self, spec: ModelSpec) -> Self {
+        self.fallback = spec;
+        self
+    }
+
+    pub fn spec(&self, model: &str) -> ModelSpec {
+        self.registered(model).unwrap_or(self.fallback)
+    }
+
+    fn registered(&self, model: &str) -> Option<ModelSpec> {
+        self.models
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, spec)| *spec)
+    }
+
+    /// whether the unknown model was not warned about yet.
+    fn first_warning(&self, model: &str) -> bool {
+        self.warned
+            .lock()
+            .map_or(true, |mut x| x.insert(model.to_string()))
+    }
+
+    pub fn tokenizer(&self, model: &str) -> Result<ModelTokenizer, Serror> {
+        let spec = match self.registered(model) {
+            Some(spec) => spec,
+            None => {
+                if self.first_warning(model) {
+                    log::warn!(
+                        "Unknown model {}, falling back to {:?}, register its spec for accurate chunks",
+                        model,
+                        self.fallback
+                    );
+                }
+                self.fallback
+            }
+        };
+        log::debug!("Using {:?} for the model {}", spec, model);
+        let bpe = match spec.encoding {
+            Encoding::Chars => None,
+            encoding => {
+                let mut bpes = self
+                    .bpes
+                    .lock()
+                    .map_err(|x| Serror::Tokenize(x.to_string()))?;
+                let bpe = match bpes.get(&encoding) {
+                    Some(bpe) => bpe.clone(),
+                    None => {
+                        let bpe = Arc::new(match encoding {
+                            Encoding::O200kBase => o200k_base()?,
+                            _ => cl100k_base()?,
+                        });
+                        bpes.insert(encoding, bpe.clone());
+                        bpe
+                    }
+                };
+                Some(bpe)
+            }
+        };
+        Ok(ModelTokenizer { bpe, spec })
+    }
+}
+
+/// [`Tokenizer`] of a model, limited to the tokens its context window leaves for the prompt.
+#[derive(Clone)]
+pub struct ModelTokenizer {
+    bpe: Option<Arc<CoreBPE>>,
+    spec: ModelSpec,
+}
+
+impl ModelTokenizer {
+    pub fn spec(&self) -> ModelSpec {
+        self.spec
+    }
+
+    /// tokens of text fitting in a request along the prompt and the output.
+    pub fn chunk_size(&self, prompt: &str, output_tokens: Option<u32>) -> Result<usize, Serror> {
+        let output_tokens = output_tokens.map_or(self.spec.output_tokens, |x| x as usize);
+        let used = self.count_tokens(prompt) + output_tokens + MESSAGE_OVERHEAD;
+        match self.spec.context_window.checked_sub(used) {
+            Some(size) if size > 0 => Ok(size),
+            _ => Err(Serror::Tokenize(format!(
+                "the prompt and {} output tokens don't fit in the context window of {} tokens",
+                output_tokens, self.spec.context_window
+            ))),
+        }
+    }
+}
+
+impl Tokenizer for ModelTokenizer {
+    fn bpe(&self) -> Option<&CoreBPE> {
+        self.bpe.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_registry_spec() {
+        let registry = TokenizerRegistry::default();
+        assert_eq!(registry.spec("gpt-4o-mini").encoding, Encoding::O200kBase);
+        assert_eq!(registry.spec("gpt-4-0613").context_window, 8_192);
+        assert_eq!(registry.spec("gpt-4-turbo-preview").context_window, 128_000);
+        assert_eq!(
+            registry.spec("gpt-3.5-turbo").encoding,
+            Encoding::Cl100kBase
+        );
+        assert_eq!(registry.spec("gpt-4-32k-0613").context_window, 32_768);
+        assert_eq!(
+            registry.spec("gpt-4.5-preview").encoding,
+            Encoding::O200kBase
+        );
+        assert_eq!(registry.spec("gpt-4.1-mini").context_window, 1_047_576);
+        assert_eq!(
+            registry.spec("chatgpt-4o-latest").encoding,
+            Encoding::O200kBase
+        );
+        assert_eq!(registry.spec("gpt-5-mini").context_window, 400_000);
+        assert_eq!(registry.spec("o1-mini").context_window, 128_000);
+        assert_eq!(registry.spec("o1-2024-12-17").context_window, 200_000);
+        assert_eq!(registry.spec("o3-mini").encoding, Encoding::O200kBase);
+        assert_eq!(registry.spec("llama3").encoding, Encoding::Chars);
+        let registry = registry.register("llama3", ModelSpec::new(Encoding::Chars, 8_192, 1_024));
+        assert_eq!(registry.spec("llama3:8b").context_window, 8_192);
+    }
+
+    #[test]
+    fn test_registry_warns_once() {
+        let registry = TokenizerRegistry::default();
+        registry.tokenizer("mistral").unwrap();
+        assert!(!registry.clone().first_warning("mistral"));
+        assert!(registry.first_warning("llama3"));
+        assert!(!registry.first_warning("llama3"));
+    }
+
+    #[test]
+    fn test_model_tokenizer() {
+        let registry = TokenizerRegistry::default();
+        let tokenizer = registry.tokenizer("gpt-4o").unwrap();
+        assert_eq!(tokenizer.count_tokens("hello world"), 2);
+        assert!(tokenizer.bpe().is_some());
+        let tokenizer = registry.tokenizer("gpt-4").unwrap();
+        assert_eq!(
+            tokenizer.chunk_size("hello world", None).unwrap(),
+            8_192 - 2 - 1_024 - MESSAGE_OVERHEAD
+        );
+        assert_eq!(
+            tokenizer.chunk_size("", Some(100)).unwrap(),
+            8_192 - 100 - MESSAGE_OVERHEAD
+        );
+        assert!(tokenizer.chunk_size("", Some(9_000)).is_err());
+        let tokenizer = registry.tokenizer("mistral").unwrap();
+        assert!(tokenizer.bpe().is_none());
+        assert_eq!(tokenizer.count_tokens("hello world!"), 3);
+    }
+}
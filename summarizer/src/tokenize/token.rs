@@ -1,75 +1,91 @@
+use super::chunk::Chunking;
 use crate::error::Serror;
 use tiktoken_rs::CoreBPE;
-pub struct Tokens<'a> {
-    tokens: Vec<usize>,
-    tokenizer: &'a CoreBPE,
-    max_tokens: usize,
-}
 
-impl<'a> Tokens<'a> {
-    pub fn detokenize(&self) -> Result<String, Serror> {
-        Ok(self.tokenizer.decode(self.tokens.clone())?)
-    }
-}
+/// ascii characters estimated to make a token, other characters count as a token each.
+const ASCII_PER_TOKEN: usize = 4;
 
-pub struct MultiTokens<'a> {
-    tokens: Vec<Vec<usize>>,
-    tokenizer: &'a CoreBPE,
-    max_tokens: usize,
+/// tokens estimated from the characters of the text, for models without a known bpe.
+fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.chars().filter(char::is_ascii).count();
+    ascii.div_ceil(ASCII_PER_TOKEN) + text.chars().count() - ascii
 }
 
-impl<'a> MultiTokens<'a> {
-    pub fn detokenize(&self) -> Result<String, Serror> {
-        let mapper = self.tokens.iter().map(|x| self.tokenizer.decode(x.clone()));
-        let mut whole_str: String = "".to_string();
-        for each_string in mapper {
-            let content = each_string?;
-            whole_str += &content
+fn split_bpe(
+    bpe: &CoreBPE,
+    text: &str,
+    max_tokens: usize,
+    step: usize,
+) -> Result<Vec<String>, Serror> {
+    let tokens = bpe.encode_with_special_tokens(text);
+    let mut chunks = vec![];
+    let mut start = 0;
+    while start < tokens.len() {
+        let end = std::cmp::min(tokens.len(), start + max_tokens);
+        chunks.push(bpe.decode(tokens[start..end].to_vec())?);
+        if end == tokens.len() {
+            break;
         }
-        Ok(whole_str)
+        start += step;
     }
+    Ok(chunks)
+}
 
-    pub fn detokenize_inarray(&self) -> Result<Vec<String>, Serror> {
-        let mapper = self.tokens.iter().map(|x| self.tokenizer.decode(x.clone()));
-        let mut contents: Vec<String> = vec![];
-        for each_string in mapper {
-            let content = each_string?;
-            contents.push(content)
+/// cuts between characters, weighing ascii characters a quarter of a token.
+fn split_estimate(text: &str, max_tokens: usize, step: usize) -> Vec<String> {
+    let chars = text
+        .char_indices()
+        .map(|(i, x)| (i, if x.is_ascii() { 1 } else { ASCII_PER_TOKEN }))
+        .collect::<Vec<_>>();
+    let offset = |i: usize| chars.get(i).map_or(text.len(), |x| x.0);
+    let mut chunks = vec![];
+    let mut start = 0;
+    while start < chars.len() {
+        let mut weight = 0;
+        let mut next = None;
+        let mut end = start;
+        while end < chars.len() && weight + chars[end].1 <= ASCII_PER_TOKEN * max_tokens {
+            weight += chars[end].1;
+            if next.is_none() && weight > ASCII_PER_TOKEN * step {
+                next = Some(end);
+            }
+            end += 1;
         }
-        Ok(contents)
+        // a character heavier than the budget still makes a chunk
+        let end = end.max(start + 1);
+        chunks.push(text[offset(start)..offset(end)].to_string());
+        if end == chars.len() {
+            break;
+        }
+        start = next.unwrap_or(end).max(start + 1);
     }
+    chunks
 }
 
 pub trait Tokenizer {
-    const MAX_N_TOKENS: usize;
-    fn bpe(&self) -> &CoreBPE;
-    fn tokenize(&self, text: &str) -> Tokens {
-        let tokens = self.bpe().encode_with_special_tokens(text);
-        Tokens {
-            tokens,
-            tokenizer: self.bpe(),
-            max_tokens: Self::MAX_N_TOKENS,
+    /// bpe of the model, `None` when tokens are estimated from the characters.
+    fn bpe(&self) -> Option<&CoreBPE>;
+    fn count_tokens(&self, text: &str) -> usize {
+        match self.bpe() {
+            Some(bpe) => bpe.encode_with_special_tokens(text).len(),
+            None => estimate_tokens(text),
         }
     }
-    fn tokenize_in_max_tokenlimit(&self, text: &str) -> Result<MultiTokens, Serror> {
-        let encoded_text = self.bpe().encode_with_special_tokens(text);
-        let mut n_tokens = self.bpe().encode_with_special_tokens(text).len();
-        let mut tokens: Vec<Vec<usize>> = vec![];
-        let mut start = 0;
-        loop {
-            if n_tokens == 0 {
-                break;
-            }
-            let max_lim = std::cmp::min(n_tokens, Self::MAX_N_TOKENS);
-            tokens.push(encoded_text[start..start + max_lim].to_vec());
-            n_tokens -= max_lim;
-            start += max_lim;
+    /// cuts the text every `max_tokens` tokens, each chunk starting `overlap` tokens before the end of the previous one.
+    fn split_tokens(
+        &self,
+        text: &str,
+        max_tokens: usize,
+        overlap: usize,
+    ) -> Result<Vec<String>, Serror> {
+        let step = max_tokens.saturating_sub(overlap).max(1);
+        match self.bpe() {
+            Some(bpe) => split_bpe(bpe, text, max_tokens, step),
+            None => Ok(split_estimate(text, max_tokens, step)),
         }
-        Ok(MultiTokens {
-            tokenizer: self.bpe(),
-            tokens: tokens,
-            max_tokens: Self::MAX_N_TOKENS,
-        })
+    }
+    fn chunk(&self, text: &str, chunking: &Chunking) -> Result<Vec<String>, Serror> {
+        chunking.chunk(self, text)
     }
 }
 
@@ -81,128 +97,52 @@ mod test {
     struct DummToken(CoreBPE);
 
     impl Tokenizer for DummToken {
-        const MAX_N_TOKENS: usize = 3;
-        fn bpe(&self) -> &CoreBPE {
-            &self.0
+        fn bpe(&self) -> Option<&CoreBPE> {
+            Some(&self.0)
         }
     }
 
-    #[test]
-    fn test_a_tokenize() {
-        let dt = DummToken(cl100k_base().unwrap());
-        assert_eq!(dt.tokenize("a").tokens, [64]);
-    }
-    #[test]
-    fn test_a_multitokenize() {
-        let dt = DummToken(cl100k_base().unwrap());
-        assert_eq!(dt.tokenize_in_max_tokenlimit("a").unwrap().tokens, [[64]]);
-    }
-    #[test]
-    fn test_abcd_tokenize() {
-        let dt = DummToken(cl100k_base().unwrap());
-        assert_eq!(dt.tokenize("abcd").tokens, [69744]);
+    struct DummEstimate;
+
+    impl Tokenizer for DummEstimate {
+        fn bpe(&self) -> Option<&CoreBPE> {
+            None
+        }
     }
 
     #[test]
-    fn test_complex_tokenize() {
-        let dt = DummToken(cl100k_base().unwrap());
+    fn test_estimate_tokens() {
+        let de = DummEstimate;
+        assert_eq!(de.count_tokens("abcdefgh"), 2);
+        assert_eq!(de.count_tokens("abcde"), 2);
+        assert_eq!(de.count_tokens("日本語"), 3);
         assert_eq!(
-            dt.tokenize("aurwfhdhbasdfiawifujbads").tokens,
-            [4202, 44183, 16373, 71, 18275, 3013, 72, 675, 333, 9832, 65, 7819]
+            de.split_tokens("abcdefghij日本", 2, 0).unwrap(),
+            ["abcdefgh", "ij日", "本"]
         );
-    }
-    #[test]
-    fn test_max_tokenlimit_tokenize() {
-        let dt = DummToken(cl100k_base().unwrap());
-        let some_vec = [
-            [4202, 44183, 16373],
-            [71, 18275, 3013],
-            [72, 675, 333],
-            [9832, 65, 7819],
-        ];
         assert_eq!(
-            dt.tokenize_in_max_tokenlimit("aurwfhdhbasdfiawifujbads")
-                .unwrap()
-                .tokens,
-            some_vec
+            de.split_tokens("abcdefghijkl", 2, 1).unwrap(),
+            ["abcdefgh", "efghijkl"]
         );
     }
+
     #[test]
-    fn test_max_tokenlimit_diff_tokenize() {
+    fn test_count_tokens() {
         let dt = DummToken(cl100k_base().unwrap());
-        let text = "asdfiawifujbads";
-        assert_eq!(
-            dt.tokenize_in_max_tokenlimit(text).unwrap().tokens,
-            [
-                [77715, 72, 675].to_vec(),
-                [333, 9832, 65].to_vec(),
-                [7819].to_vec()
-            ]
-        );
-        assert_eq!(dt.tokenize(text).tokens.len(), 7);
-    }
-    #[test]
-    fn test_detokenize() {
-        let dt = cl100k_base().unwrap();
-        let tokens = Tokens {
-            tokenizer: &dt,
-            tokens: [64].to_vec(),
-            max_tokens: 10,
-        };
-        assert_eq!(tokens.detokenize().unwrap(), "a");
-    }
-    #[test]
-    fn test_detokenize_sequence() {
-        let dt = cl100k_base().unwrap();
-        let tokens = Tokens {
-            tokenizer: &dt,
-            tokens: [69744].to_vec(),
-            max_tokens: 10,
-        };
-        assert_eq!(tokens.detokenize().unwrap(), "abcd");
-    }
-    #[test]
-    fn test_detokenize_complex() {
-        let dt = cl100k_base().unwrap();
-        let tokens = Tokens {
-            tokenizer: &dt,
-            tokens: [
-                4202, 44183, 16373, 71, 18275, 3013, 72, 675, 333, 9832, 65, 7819,
-            ]
-            .to_vec(),
-            max_tokens: 10,
-        };
-        assert_eq!(tokens.detokenize().unwrap(), "aurwfhdhbasdfiawifujbads");
-    }
-    #[test]
-    fn test_detokenize_complex_multi() {
-        let dt = cl100k_base().unwrap();
-        let tokens = MultiTokens {
-            tokenizer: &dt,
-            tokens: [
-                [4202, 44183, 16373].to_vec(),
-                [71, 18275, 3013].to_vec(),
-                [72, 675, 333].to_vec(),
-                [9832, 65, 7819].to_vec(),
-            ]
-            .to_vec(),
-            max_tokens: 10,
-        };
-        assert_eq!(tokens.detokenize().unwrap(), "aurwfhdhbasdfiawifujbads");
+        assert_eq!(dt.count_tokens("a"), 1);
+        assert_eq!(dt.count_tokens("abcd"), 1);
+        assert_eq!(dt.count_tokens("aurwfhdhbasdfiawifujbads"), 12);
     }
+
     #[test]
-    fn test_detokenize_complex_multi_different_lengths() {
-        let dt = cl100k_base().unwrap();
-        let tokens = MultiTokens {
-            tokenizer: &dt,
-            tokens: [
-                [77715, 72, 675].to_vec(),
-                [333, 9832, 65].to_vec(),
-                [7819].to_vec(),
-            ]
-            .to_vec(),
-            max_tokens: 10,
-        };
-        assert_eq!(tokens.detokenize().unwrap(), "asdfiawifujbads");
+    fn test_split_tokens() {
+        let dt = DummToken(cl100k_base().unwrap());
+        let text = "aurwfhdhbasdfiawifujbads";
+        let chunks = dt.split_tokens(text, 3, 0).unwrap();
+        assert_eq!(chunks, ["aurwfhd", "hbasdf", "iawif", "ujbads"]);
+        assert_eq!(dt.split_tokens("asdfiawifujbads", 3, 0).unwrap().len(), 3);
+        let chunks = dt.split_tokens(text, 3, 1).unwrap();
+        assert_eq!(chunks.len(), 6);
+        assert!(chunks.iter().all(|x| dt.count_tokens(x) <= 3));
     }
 }
@@ -1,132 +1,166 @@
 use crate::error::Serror;
-use regex::Regex;
-use reqwest::Client;
-use youtube_transcript::{Transcript, YoutubeBuilder};
-pub struct Youtube<'a> {
-    link: &'a str,
-    video_id_extractor: Regex,
+use std::sync::Arc;
+use youtube_transcript::{
+    Chapter, Transcript, TranscriptCore, VideoId, VideoMetadata, YoutubeBuilder,
+};
+pub struct Youtube {
+    video_id: VideoId,
+    loader: Arc<youtube_transcript::Youtube>,
 }
-impl<'a> Youtube<'a> {
-    pub fn link(link: &'a str) -> Result<Self, Serror> {
-        Ok(Self {
-            link,
-            video_id_extractor: Regex::new(r"v=([^&]+)")?,
-        })
+impl Youtube {
+    pub fn link(link: &str) -> Result<Self, Serror> {
+        Self::link_with(link, &Arc::new(YoutubeBuilder::default().try_build()?))
     }
 
-    fn video_id(&self) -> Result<&str, Serror> {
-        let capture = self
-            .video_id_extractor
-            .captures(self.link)
-            .ok_or(Serror::Youtubefetch(format!(
-                "Cannot capture the video id in the url: {}",
-                self.link
-            )))?
-            .get(1)
-            .ok_or(Serror::Youtubefetch(format!(
-                "Cannot capture the video id in the url: {}",
-                self.link
-            )))?;
-
-        Ok(capture.as_str())
+    /// Youtube link fetched with a shared loader, reusing its http client across links
+    pub fn link_with(
+        link: &str,
+        loader: &Arc<youtube_transcript::Youtube>,
+    ) -> Result<Self, Serror> {
+        Ok(Self {
+            video_id: link.parse::<VideoId>()?,
+            loader: loader.clone(),
+        })
     }
 
     pub async fn content(&self) -> Result<YoutubeContent, Serror> {
         // Get Youtube Body
-        let client = Client::default();
-        let response = client.get(self.link).send().await?;
-        let content = response.text().await?;
+        let content = self.loader.watch_page(&self.video_id).await?;
 
         Ok(YoutubeContent {
-            content: content,
-            video_id: self.video_id()?.to_string(),
+            content,
+            video_id: self.video_id.to_string(),
+            loader: self.loader.clone(),
         })
     }
 }
 
-#[derive(Debug)]
 pub struct YoutubeContent {
     content: String,
     pub(crate) video_id: String,
+    loader: Arc<youtube_transcript::Youtube>,
 }
 
 impl YoutubeContent {
     /// Get transcript
     pub async fn transcirpt(&self) -> Result<Transcript, Serror> {
-        YoutubeBuilder::default()
-            .build()
+        self.loader
             .transcript_from_text(&self.content)
             .await
-            .map_err(|x| Serror::Youtubefetch(x.to_string()))
+            .map_err(Serror::from)
+    }
+
+    /// Get metadata of the video
+    pub fn metadata(&self) -> Result<VideoMetadata, Serror> {
+        self.loader
+            .metadata_from_text(&self.content)
+            .map_err(Serror::from)
     }
 
     /// Get image link
     pub fn image_link(&self) -> String {
-        format!("https://img.youtube.com/vi/{}/0.jpg", self.video_id)
+        self.metadata()
+            .ok()
+            .and_then(|x| x.best_thumbnail().map(|x| x.url.clone()))
+            .unwrap_or_else(|| format!("https://img.youtube.com/vi/{}/0.jpg", self.video_id))
     }
 
     /// Get title
     pub fn title(&self) -> Result<Option<String>, Serror> {
-        let c = Regex::new(r"<title>(.+)</title>")?.captures(&self.content);
-        if let Some(capture) = c {
-            if let Some(match_str) = capture.get(1) {
-                return Ok(Some(match_str.as_str().to_string()));
-            }
-        }
-        Ok(None)
+        Ok(self.metadata().ok().map(|x| x.title))
+    }
+
+    /// Get chapters of the video, empty if the video has none
+    pub fn chapters(&self) -> Vec<Chapter> {
+        self.loader
+            .chapters_from_text(&self.content)
+            .unwrap_or_default()
     }
 
     /// Get transcript in text
     pub async fn transcript_text(&self) -> Result<String, Serror> {
         let transcript = self.transcirpt().await?;
-        Ok(transcript
-            .transcripts
-            .into_iter()
-            .map(|x| format!("{} ", x.text))
-            .collect::<String>())
+        Ok(text(&transcript.transcripts))
     }
 }
 
+/// Joins the transcript texts into a single text
+pub(crate) fn text(transcripts: &[TranscriptCore]) -> String {
+    transcripts
+        .iter()
+        .map(|x| format!("{} ", x.text))
+        .collect::<String>()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use youtube_transcript::mock::MockServer;
+    #[test]
+    fn test_link() {
+        for link in [
+            "https://www.youtube.com/watch?v=GJLlxj_dtq8",
+            "https://youtu.be/GJLlxj_dtq8",
+            "https://m.youtube.com/shorts/GJLlxj_dtq8",
+            "GJLlxj_dtq8",
+        ] {
+            assert_eq!(
+                Youtube::link(link).unwrap().video_id.as_str(),
+                "GJLlxj_dtq8"
+            );
+        }
+        assert!(Youtube::link("https://example.com/watch?v=GJLlxj_dtq8").is_err());
+    }
+
     #[tokio::test]
-    #[ignore = "Requires mocking youtube response"]
     async fn test_content() {
-        let content = Youtube::link("https://www.youtube.com/watch?v=GJLlxj_dtq8")
-            .unwrap()
-            .content()
-            .await
-            .unwrap()
-            .transcript_text()
-            .await
-            .unwrap();
-        println!("{}", content);
+        let server = MockServer::fixtures().await.unwrap();
+        let content = Youtube::link_with(
+            "https://www.youtube.com/watch?v=GJLlxj_dtq8",
+            &Arc::new(server.builder().build()),
+        )
+        .unwrap()
+        .content()
+        .await
+        .unwrap()
+        .transcript_text()
+        .await
+        .unwrap();
+        assert!(content.starts_with("Hey, how's it going Dave 2d here? "));
     }
 
     #[tokio::test]
-    #[ignore = "Requires mocking youtube response"]
     async fn test_title() {
-        let title = Youtube::link("https://www.youtube.com/watch?v=GJLlxj_dtq8")
-            .unwrap()
-            .content()
-            .await
-            .unwrap()
-            .title()
-            .unwrap()
-            .expect("No title found");
-        println!("{}", title);
+        let server = MockServer::fixtures().await.unwrap();
+        let title = Youtube::link_with(
+            "https://www.youtube.com/watch?v=GJLlxj_dtq8",
+            &Arc::new(server.builder().build()),
+        )
+        .unwrap()
+        .content()
+        .await
+        .unwrap()
+        .title()
+        .unwrap()
+        .expect("No title found");
+        assert_eq!(title, "Microsoft Surface Go - Review");
     }
 
     #[tokio::test]
-    #[ignore = "Requires mocking youtube response"]
     async fn test_image() {
-        let image_link = Youtube::link("https://www.youtube.com/watch?v=GJLlxj_dtq8")
-            .unwrap()
-            .content()
-            .await
-            .unwrap()
-            .image_link();
-        println!("{}", image_link);
+        let server = MockServer::fixtures().await.unwrap();
+        let image_link = Youtube::link_with(
+            "https://www.youtube.com/watch?v=GJLlxj_dtq8",
+            &Arc::new(server.builder().build()),
+        )
+        .unwrap()
+        .content()
+        .await
+        .unwrap()
+        .image_link();
+        assert_eq!(
+            image_link,
+            "https://i.ytimg.com/vi/GJLlxj_dtq8/maxresdefault.jpg"
+        );
     }
 }
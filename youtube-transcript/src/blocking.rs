@@ -0,0 +1,124 @@
+//! Synchronous api for programs without an async runtime.
+//!
+//! ```rust
+//! let youtube_loader = YoutubeBuilder::default().build_blocking();
+//! let transcript = youtube_loader.transcript("https://www.youtube.com/watch?v=RcYjXbSJBN8")?;
+//! ```
+use crate::{
+    error, Caption, Chapter, Transcript, VideoId, VideoMetadata, Youtube as AsyncYoutube,
+    YoutubeBuilder,
+};
+use std::future::Future;
+use tokio::runtime::{Builder, Runtime};
+
+/// Youtube blocking the current thread on every request, see [`crate::Youtube`] for the async version.
+///
+/// Requests are driven by a runtime owned by the instance, so its methods must not be called from async code.
+pub struct Youtube {
+    youtube: AsyncYoutube,
+    runtime: Runtime,
+}
+
+impl Youtube {
+    fn block_on<T>(&self, future: impl Future<Output = T>) -> T {
+        self.runtime.block_on(future)
+    }
+
+    /// extracts [`Transcript`] from the video link or video id provided.
+    pub fn transcript(&self, url: &str) -> Result<Transcript, error::Error> {
+        self.block_on(self.youtube.transcript(url))
+    }
+
+    /// lists every caption track available for the video link or video id provided.
+    pub fn caption_tracks(&self, url: &str) -> Result<Vec<Caption>, error::Error> {
+        self.block_on(self.youtube.caption_tracks(url))
+    }
+
+    /// fetches the raw html of the watch page of the video.
+    pub fn watch_page(&self, video_id: &VideoId) -> Result<String, error::Error> {
+        self.block_on(self.youtube.watch_page(video_id))
+    }
+
+    /// lists every caption track available in the youtube raw html text provided.
+    pub fn caption_tracks_from_text(&self, text: &str) -> Result<Vec<Caption>, error::Error> {
+        self.youtube.caption_tracks_from_text(text)
+    }
+
+    /// lists the videos of the playlist link or playlist id provided, in playlist order.
+    pub fn playlist_video_ids(&self, url: &str) -> Result<Vec<VideoId>, error::Error> {
+        self.block_on(self.youtube.playlist_video_ids(url))
+    }
+
+    /// lists the videos uploaded by the channel link, handle or channel id provided, newest first.
+    pub fn channel_uploads(&self, url: &str) -> Result<Vec<VideoId>, error::Error> {
+        self.block_on(self.youtube.channel_uploads(url))
+    }
+
+    /// extracts [`VideoMetadata`] of the video link or video id provided.
+    pub fn metadata(&self, url: &str) -> Result<VideoMetadata, error::Error> {
+        self.block_on(self.youtube.metadata(url))
+    }
+
+    /// extracts [`VideoMetadata`] from the youtube raw html text provided.
+    pub fn metadata_from_text(&self, text: &str) -> Result<VideoMetadata, error::Error> {
+        self.youtube.metadata_from_text(text)
+    }
+
+    /// extracts the [`Chapter`]s of the video link or video id provided.
+    pub fn chapters(&self, url: &str) -> Result<Vec<Chapter>, error::Error> {
+        self.block_on(self.youtube.chapters(url))
+    }
+
+    /// extracts the [`Chapter`]s from the youtube raw html text provided.
+    pub fn chapters_from_text(&self, text: &str) -> Result<Vec<Chapter>, error::Error> {
+        self.youtube.chapters_from_text(text)
+    }
+
+    /// extracts [`Transcript`] from the youtube raw html text provided.
+    pub fn transcript_from_text(&self, text: &str) -> Result<Transcript, error::Error> {
+        self.block_on(self.youtube.transcript_from_text(text))
+    }
+
+    /// extracts [`Transcript`] from a saved timedtext response without any network call.
+    pub fn transcript_from_timedtext(&self, text: &str) -> Result<Transcript, error::Error> {
+        self.youtube.transcript_from_timedtext(text)
+    }
+}
+
+impl YoutubeBuilder {
+    /// build blocking [`Youtube`] from the builder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the http client or the runtime driving it cannot be initialized.
+    pub fn build_blocking(self) -> Youtube {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Cannot initialize the runtime");
+        Youtube {
+            youtube: self.build(),
+            runtime,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mock::MockServer;
+
+    #[test]
+    fn test_blocking_transcript() {
+        // the mock server runs on its own runtime while the blocking api drives requests on the test thread.
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let server = runtime.block_on(MockServer::fixtures()).unwrap();
+        let youtube = server.builder().build_blocking();
+        let transcript = youtube.transcript("GJLlxj_dtq8").unwrap();
+        assert_eq!(
+            transcript.transcripts[0].text,
+            "Hey, how's it going Dave 2d here?"
+        );
+        let metadata = youtube.metadata("GJLlxj_dtq8").unwrap();
+        assert_eq!(metadata.channel_name, "Dave2D");
+    }
+}
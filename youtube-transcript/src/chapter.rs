@@ -0,0 +1,263 @@
+use crate::error;
+use crate::parser::{Transcript, TranscriptCore};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// youtube only shows chapters of a description with at least three timestamps, the first one at 0:00.
+const MIN_DESCRIPTION_CHAPTERS: usize = 3;
+/// characters separating the timestamp from the title in a description line. Ex: "0:00 - Intro"
+const TITLE_SEPARATORS: [char; 7] = ['-', '–', '—', ':', '|', '•', ')'];
+
+/// Chapter of a youtube video.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    /// title of the chapter. Ex: "Intro"
+    pub title: String,
+    /// starting time of the chapter in the whole video.
+    pub start: Duration,
+    /// ending time of the chapter, `None` for the last chapter when the duration of the video is unknown.
+    pub end: Option<Duration>,
+}
+
+/// parses timestamps like "1:02:03", "02:03" or "2:03".
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let parts = timestamp.split(':').collect::<Vec<_>>();
+    if !(2..=3).contains(&parts.len())
+        || parts
+            .iter()
+            .any(|x| x.is_empty() || x.len() > 2 || !x.bytes().all(|x| x.is_ascii_digit()))
+    {
+        return None;
+    }
+    let secs = parts
+        .iter()
+        .try_fold(0u64, |acc, x| Some(acc * 60 + x.parse::<u64>().ok()?))?;
+    Some(Duration::from_secs(secs))
+}
+
+/// parses a description line starting or ending with a timestamp. Ex: "0:00 Intro" or "Intro (12:30)"
+fn parse_line(line: &str) -> Option<(Duration, String)> {
+    let line = line
+        .trim()
+        .trim_start_matches(['•', '-', '*', '(', '['])
+        .trim();
+    let (timestamp, title) = match line.split_once(char::is_whitespace) {
+        Some((first, rest)) if parse_timestamp(first).is_some() => (first, rest),
+        _ => {
+            let (rest, last) = line.rsplit_once(char::is_whitespace)?;
+            (last.trim_matches(['(', ')', '[', ']']), rest)
+        }
+    };
+    let start = parse_timestamp(timestamp)?;
+    let title = title.trim().trim_matches(TITLE_SEPARATORS).trim();
+    (!title.is_empty()).then(|| (start, title.to_string()))
+}
+
+/// sets the end of every chapter to the start of the next one and the last one to the duration of the video.
+fn with_ends(starts: Vec<(Duration, String)>, duration: Option<Duration>) -> Vec<Chapter> {
+    let ends = starts
+        .iter()
+        .skip(1)
+        .map(|(start, _)| Some(*start))
+        .chain([duration])
+        .collect::<Vec<_>>();
+    starts
+        .into_iter()
+        .zip(ends)
+        .map(|((start, title), end)| Chapter { title, start, end })
+        .collect()
+}
+
+impl Chapter {
+    /// parses chapters from the timestamps of a video description. Follows youtube's rules: the first chapter
+    /// starts at 0:00, timestamps are ascending and there are at least three of them.
+    pub fn from_description(description: &str, duration: Option<Duration>) -> Vec<Chapter> {
+        let mut starts: Vec<(Duration, String)> = Vec::new();
+        for (start, title) in description.lines().filter_map(parse_line) {
+            match starts.last() {
+                None if start != Duration::ZERO => continue,
+                Some((last, _)) if start <= *last => continue,
+                _ => starts.push((start, title)),
+            }
+        }
+        if starts.len() < MIN_DESCRIPTION_CHAPTERS {
+            return Vec::new();
+        }
+        with_ends(starts, duration)
+    }
+
+    /// parses the chapter markers of the player from the watch page html.
+    pub(crate) fn from_html(
+        html: &str,
+        from: &str,
+        duration: Option<Duration>,
+    ) -> Result<Vec<Chapter>, error::Error> {
+        let mut starts = Vec::new();
+        for (_, rest) in html
+            .match_indices(from)
+            .map(|(i, x)| html.split_at(i + x.len()))
+        {
+            let renderer = serde_json::Deserializer::from_str(rest)
+                .into_iter::<ChapterRenderer>()
+                .next()
+                .ok_or_else(|| error::Error::ParseError("Cannot parse chapter".into()))?
+                .map_err(|x| error::Error::ParseError(format!("{}", x)))?;
+            let start = Duration::from_millis(renderer.time_range_start_millis);
+            if starts.iter().all(|(x, _)| *x != start) {
+                starts.push((start, renderer.title.simple_text));
+            }
+        }
+        starts.sort_by_key(|(start, _)| *start);
+        Ok(with_ends(starts, duration))
+    }
+}
+
+#[derive(Deserialize)]
+struct ChapterTitle {
+    #[serde(rename(deserialize = "simpleText"), default)]
+    simple_text: String,
+}
+
+#[derive(Deserialize)]
+struct ChapterRenderer {
+    title: ChapterTitle,
+    #[serde(rename(deserialize = "timeRangeStartMillis"))]
+    time_range_start_millis: u64,
+}
+
+/// Transcript of a single [`Chapter`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ChapterTranscript {
+    /// chapter of the video.
+    pub chapter: Chapter,
+    /// List of transcript texts of the chapter in [`TranscriptCore`] format
+    pub transcripts: Vec<TranscriptCore>,
+}
+
+impl Transcript {
+    /// transcript texts starting within `start` (inclusive) and `end` (exclusive).
+    pub fn slice(&self, start: Duration, end: Duration) -> Transcript {
+        Transcript {
+            transcripts: self
+                .transcripts
+                .iter()
+                .filter(|x| x.start >= start && x.start < end)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// splits the transcript into one [`ChapterTranscript`] per chapter.
+    pub fn by_chapters(&self, chapters: &[Chapter]) -> Vec<ChapterTranscript> {
+        chapters
+            .iter()
+            .map(|chapter| ChapterTranscript {
+                chapter: chapter.clone(),
+                transcripts: self
+                    .slice(chapter.start, chapter.end.unwrap_or(Duration::MAX))
+                    .transcripts,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DESCRIPTION: &str = "Surface Go review.
+
+Chapters:
+0:00 Intro
+0:45 - Build quality
+1:30 Screen & speakers
+(Ok, 1:00 out of order)
+2:35 Keyboard | trackpad
+Performance (03:41)
+Visit https://example.com at 10:00am";
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("0:00"), Some(Duration::ZERO));
+        assert_eq!(parse_timestamp("02:35"), Some(Duration::from_secs(155)));
+        assert_eq!(parse_timestamp("1:02:03"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_timestamp("10:00am"), None);
+        assert_eq!(parse_timestamp("123:00"), None);
+    }
+
+    #[test]
+    fn test_chapters_from_description() {
+        let chapters = Chapter::from_description(DESCRIPTION, Some(Duration::from_secs(312)));
+        let titles = chapters
+            .iter()
+            .map(|x| x.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            [
+                "Intro",
+                "Build quality",
+                "Screen & speakers",
+                "Keyboard | trackpad",
+                "Performance"
+            ]
+        );
+        assert_eq!(chapters[1].start, Duration::from_secs(45));
+        assert_eq!(chapters[1].end, Some(Duration::from_secs(90)));
+        assert_eq!(chapters[4].start, Duration::from_secs(221));
+        assert_eq!(chapters[4].end, Some(Duration::from_secs(312)));
+    }
+
+    #[test]
+    fn test_chapters_from_description_invalid() {
+        assert!(Chapter::from_description("0:45 Intro\n1:30 Screen\n2:35 End", None).is_empty());
+        assert!(Chapter::from_description("0:00 Intro\n1:30 Screen", None).is_empty());
+    }
+
+    #[test]
+    fn test_chapters_from_html() {
+        let html = r#"{"markersMap":[{"key":"DESCRIPTION_CHAPTERS","value":{"chapters":[{"chapterRenderer":{"title":{"simpleText":"Intro"},"timeRangeStartMillis":0,"thumbnail":{"thumbnails":[]}}},{"chapterRenderer":{"title":{"simpleText":"Build quality"},"timeRangeStartMillis":45000}}]}}]}"#;
+        let chapters = Chapter::from_html(html, "\"chapterRenderer\":", None).unwrap();
+        assert_eq!(
+            chapters,
+            [
+                Chapter {
+                    title: "Intro".into(),
+                    start: Duration::ZERO,
+                    end: Some(Duration::from_secs(45)),
+                },
+                Chapter {
+                    title: "Build quality".into(),
+                    start: Duration::from_secs(45),
+                    end: None,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_by_chapters() {
+        let core = |start: u64| TranscriptCore {
+            text: format!("at {start}"),
+            start: Duration::from_secs(start),
+            duration: Duration::from_secs(2),
+            words: Vec::new(),
+        };
+        let transcript = Transcript {
+            transcripts: vec![core(0), core(30), core(45), core(100), core(300)],
+        };
+        assert_eq!(
+            transcript
+                .slice(Duration::from_secs(30), Duration::from_secs(100))
+                .transcripts,
+            vec![core(30), core(45)]
+        );
+        let chapters = Chapter::from_description(DESCRIPTION, None);
+        let by_chapters = transcript.by_chapters(&chapters);
+        assert_eq!(by_chapters.len(), 5);
+        assert_eq!(by_chapters[0].transcripts, vec![core(0), core(30)]);
+        assert_eq!(by_chapters[1].transcripts, vec![core(45)]);
+        assert_eq!(by_chapters[2].transcripts, vec![core(100)]);
+        assert_eq!(by_chapters[4].transcripts, vec![core(300)]);
+    }
+}
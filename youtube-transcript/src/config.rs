@@ -1,9 +1,13 @@
-use once_cell::sync::Lazy;
+use crate::http::HttpConfig;
+use crate::innertube::InnertubeConfig;
+use crate::language::LanguageTag;
 use strum_macros::{EnumString, IntoStaticStr};
 
 pub struct HTMLParserConfig {
     pub from: &'static str,
     pub to: &'static str,
+    pub player_response: &'static str,
+    pub chapter: &'static str,
 }
 
 impl Default for HTMLParserConfig {
@@ -11,10 +15,14 @@ impl Default for HTMLParserConfig {
         Self {
             from: "playerCaptionsTracklistRenderer\":",
             to: "},\"videoDetails\"",
+            player_response: "ytInitialPlayerResponse = ",
+            chapter: "\"chapterRenderer\":",
         }
     }
 }
 
+/// Language codes of common caption tracks on youtube, convertible into a [`LanguageTag`].
+/// Other languages, regions or scripts are expressed by parsing a [`LanguageTag`]. Ex: "pt-BR"
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy, Default, EnumString, IntoStaticStr)]
 pub enum LangCode {
@@ -26,7 +34,7 @@ pub enum LangCode {
     bg,
     /// Catalan
     ca,
-    /// CN Chinese, Simplified
+    /// Chinese
     zh,
     /// Croatian
     hr,
@@ -39,7 +47,7 @@ pub enum LangCode {
     /// English
     #[default]
     en,
-    /// GB English, UK
+    /// Filipino
     fil,
     /// Finnish
     fi,
@@ -81,9 +89,9 @@ pub enum LangCode {
     no,
     /// Polish
     pl,
-    /// BR Portuguese, Brazil
+    /// Portuguese
     pt,
-    /// PT Portuguese, Portugal
+    /// Romanian
     ro,
     /// Russian
     ru,
@@ -115,17 +123,105 @@ pub enum LangCode {
     vi,
 }
 
+/// Policy for choosing between uploaded and auto-generated caption tracks of the same language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum CaptionPolicy {
+    /// prefer captions uploaded by the author, fallback to auto-generated ones.
+    #[default]
+    PreferManual,
+    /// prefer auto-generated captions, fallback to uploaded ones.
+    PreferAsr,
+    /// only use captions uploaded by the author.
+    ManualOnly,
+}
+
+/// Format of the timedtext response requested from youtube.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum TranscriptFormat {
+    /// legacy `<transcript><text start dur>` xml format.
+    #[default]
+    Xml,
+    /// json format with word level segments.
+    Json3,
+    /// xml format with `<p t d>` paragraphs and word level segments.
+    Srv3,
+}
+
+impl TranscriptFormat {
+    /// value of the `fmt` query parameter of the timedtext url.
+    pub(crate) fn fmt(&self) -> Option<&'static str> {
+        match self {
+            TranscriptFormat::Xml => None,
+            TranscriptFormat::Json3 => Some("json3"),
+            TranscriptFormat::Srv3 => Some("srv3"),
+        }
+    }
+
+    /// format of a saved timedtext response, detected from its root element.
+    pub fn detect(text: &str) -> Option<Self> {
+        let text = text.trim_start();
+        if text.starts_with('{') {
+            Some(TranscriptFormat::Json3)
+        } else if text.contains("<timedtext") {
+            Some(TranscriptFormat::Srv3)
+        } else if text.contains("<transcript") {
+            Some(TranscriptFormat::Xml)
+        } else {
+            None
+        }
+    }
+}
+
+/// Source of the caption tracks of a video.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum Backend {
+    /// scrapes the caption tracks from the html of the watch page.
+    #[default]
+    Html,
+    /// reads the caption tracks from youtube's innertube player api, falls back to [`Backend::Html`] when the
+    /// response cannot be parsed or the request fails. Playability errors and rate limiting are returned as is.
+    Innertube,
+}
+
 /// configuration that contains anchor points for identifying captions from youtube's html webpage.
 pub struct Config {
     pub(crate) parser: HTMLParserConfig,
-    pub(crate) lang_code: LangCode,
+    pub(crate) innertube: InnertubeConfig,
+    pub(crate) http: HttpConfig,
+    /// scheme and host serving the watch page and the innertube api. Ex: "https://www.youtube.com"
+    pub(crate) watch_host: String,
+    /// scheme and host serving the timedtext captions.
+    pub(crate) timedtext_host: String,
+    pub(crate) backend: Backend,
+    pub(crate) lang_codes: Vec<LanguageTag>,
+    pub(crate) policy: CaptionPolicy,
+    pub(crate) translate_to: Option<LanguageTag>,
+    pub(crate) format: TranscriptFormat,
+    pub(crate) normalize: bool,
+    pub(crate) strip_markers: bool,
 }
 
+/// host of every youtube request by default.
+const YOUTUBE_HOST: &str = "https://www.youtube.com";
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             parser: HTMLParserConfig::default(),
-            lang_code: LangCode::default(),
+            innertube: InnertubeConfig::default(),
+            http: HttpConfig::default(),
+            watch_host: YOUTUBE_HOST.into(),
+            timedtext_host: YOUTUBE_HOST.into(),
+            backend: Backend::default(),
+            lang_codes: vec![LanguageTag::default()],
+            policy: CaptionPolicy::default(),
+            translate_to: None,
+            format: TranscriptFormat::default(),
+            normalize: true,
+            strip_markers: false,
         }
     }
 }
@@ -1,15 +1,128 @@
-use std::{error::Error as StdError, fmt::Display};
+use std::{
+    error::Error as StdError,
+    fmt::Display,
+    num::{ParseFloatError, ParseIntError},
+};
 
+/// Errors returned while fetching and parsing youtube transcripts.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    #[allow(dead_code)]
+    /// response could not be parsed.
     ParseError(String),
+    /// anchors of the watch page were not found, youtube probably changed its page layout.
+    LayoutChanged(String),
+    /// link or id does not contain a valid youtube video id.
+    InvalidVideoId(String),
+    /// link or id does not contain a valid youtube playlist id.
+    InvalidPlaylistId(String),
+    /// link does not belong to a youtube channel.
+    ChannelNotFound(String),
+    /// language is not a valid BCP-47 language tag.
+    InvalidLanguageTag(String),
+    /// value of an http header set on the builder is not valid. Ex: an accept language with a newline
+    InvalidHeader(String),
+    /// search query is not a valid regular expression.
+    InvalidQuery(String),
+    /// none of the requested languages have a caption track.
+    LanguageNotFound {
+        /// language codes requested.
+        requested: Vec<String>,
+        /// language codes of the caption tracks available for the video.
+        available: Vec<String>,
+    },
+    /// video has no caption tracks.
+    CaptionsDisabled,
+    /// video is private, holds youtube's reason.
+    VideoPrivate(String),
+    /// video requires signing in to confirm the viewer's age, holds youtube's reason.
+    AgeRestricted(String),
+    /// video cannot be played, ex: removed or blocked in the country. Holds youtube's reason.
+    VideoUnavailable(String),
+    /// youtube is rate limiting the requests or asking to confirm they are not from a bot.
+    TooManyRequests,
+    /// http request failed.
+    Network(reqwest::Error),
+}
+
+impl Error {
+    /// whether the same request may succeed when retried later.
+    // `Option::is_none_or` needs rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::TooManyRequests => true,
+            Self::Network(x) => x.status().map_or(true, |x| x.is_server_error()),
+            _ => false,
+        }
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Self::ParseError(x) => write!(f, "Cannot parse response: {}", x),
+            Self::LayoutChanged(x) => write!(f, "Youtube page layout changed: {}", x),
+            Self::InvalidVideoId(x) => write!(f, "Cannot find a video id in: {}", x),
+            Self::InvalidPlaylistId(x) => write!(f, "Cannot find a playlist id in: {}", x),
+            Self::ChannelNotFound(x) => write!(f, "Cannot find a channel for: {}", x),
+            Self::InvalidLanguageTag(x) => write!(f, "Invalid language tag: {}", x),
+            Self::InvalidHeader(x) => write!(f, "Invalid http header: {}", x),
+            Self::InvalidQuery(x) => write!(f, "Invalid search query: {}", x),
+            Self::LanguageNotFound {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Cannot find lang {}, available: {}",
+                requested.join(", "),
+                available.join(", ")
+            ),
+            Self::CaptionsDisabled => write!(f, "Captions are disabled for the video"),
+            Self::VideoPrivate(x) => write!(f, "Video is private: {}", x),
+            Self::AgeRestricted(x) => write!(f, "Video is age restricted: {}", x),
+            Self::VideoUnavailable(x) => write!(f, "Video is unavailable: {}", x),
+            Self::TooManyRequests => write!(f, "Too many requests to youtube"),
+            Self::Network(x) => write!(f, "Network: {}", x),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Network(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Network(value)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Self::ParseError(value.to_string())
+    }
+}
+
+impl From<roxmltree::Error> for Error {
+    fn from(value: roxmltree::Error) -> Self {
+        Self::ParseError(value.to_string())
     }
 }
 
-impl StdError for Error {}
+impl From<ParseFloatError> for Error {
+    fn from(value: ParseFloatError) -> Self {
+        Self::ParseError(value.to_string())
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(value: ParseIntError) -> Self {
+        Self::ParseError(value.to_string())
+    }
+}
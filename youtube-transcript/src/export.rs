@@ -0,0 +1,108 @@
+use crate::parser::Transcript;
+use crate::utils::{escape_xml, to_timestamp};
+
+impl Transcript {
+    /// renders the transcript in SubRip (.srt) format.
+    pub fn to_srt(&self) -> String {
+        self.transcripts
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    i + 1,
+                    to_timestamp(&x.start, ','),
+                    to_timestamp(&(x.start + x.duration), ','),
+                    x.text
+                )
+            })
+            .collect()
+    }
+
+    /// renders the transcript in WebVTT (.vtt) format.
+    pub fn to_vtt(&self) -> String {
+        let cues = self
+            .transcripts
+            .iter()
+            .map(|x| {
+                format!(
+                    "{} --> {}\n{}\n\n",
+                    to_timestamp(&x.start, '.'),
+                    to_timestamp(&(x.start + x.duration), '.'),
+                    escape_xml(&x.text)
+                )
+            })
+            .collect::<String>();
+        format!("WEBVTT\n\n{cues}")
+    }
+
+    /// renders the transcript in Timed Text Markup Language (.ttml) format.
+    pub fn to_ttml(&self) -> String {
+        let paragraphs = self
+            .transcripts
+            .iter()
+            .map(|x| {
+                format!(
+                    "      <p begin=\"{}\" end=\"{}\">{}</p>\n",
+                    to_timestamp(&x.start, '.'),
+                    to_timestamp(&(x.start + x.duration), '.'),
+                    escape_xml(&x.text).replace('\n', "<br/>")
+                )
+            })
+            .collect::<String>();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<tt xmlns=\"http://www.w3.org/ns/ttml\">\n  <body>\n    <div>\n{paragraphs}    </div>\n  </body>\n</tt>\n"
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::TranscriptCore;
+    use std::time::Duration;
+
+    fn transcript() -> Transcript {
+        Transcript {
+            transcripts: vec![
+                TranscriptCore {
+                    text: "Hey, how's it going".into(),
+                    start: Duration::from_millis(0),
+                    duration: Duration::from_millis(1540),
+                    words: Vec::new(),
+                },
+                TranscriptCore {
+                    text: "Surface <go> & more".into(),
+                    start: Duration::from_millis(3_661_540),
+                    duration: Duration::from_millis(4160),
+                    words: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_srt() {
+        assert_eq!(
+            transcript().to_srt(),
+            "1\n00:00:00,000 --> 00:00:01,540\nHey, how's it going\n\n2\n01:01:01,540 --> 01:01:05,700\nSurface <go> & more\n\n"
+        );
+    }
+
+    #[test]
+    fn test_vtt() {
+        assert_eq!(
+            transcript().to_vtt(),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.540\nHey, how's it going\n\n01:01:01.540 --> 01:01:05.700\nSurface &lt;go&gt; &amp; more\n\n"
+        );
+    }
+
+    #[test]
+    fn test_ttml() {
+        let ttml = transcript().to_ttml();
+        assert!(
+            ttml.contains("<p begin=\"00:00:00.000\" end=\"00:00:01.540\">Hey, how's it going</p>")
+        );
+        assert!(roxmltree::Document::parse(&ttml).is_ok());
+    }
+}
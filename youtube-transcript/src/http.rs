@@ -0,0 +1,98 @@
+use crate::error;
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT_LANGUAGE, USER_AGENT};
+use reqwest::{Client, Proxy, Url};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// user agent of a desktop browser, youtube serves a stripped down page to unknown agents.
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+/// cookie accepting youtube's consent page shown to visitors from the EU.
+const CONSENT_COOKIE: &str = "SOCS=CAI; Domain=.youtube.com; Path=/";
+/// url the consent cookie is registered for.
+const CONSENT_URL: &str = "https://www.youtube.com";
+
+/// configuration of the http client used for requesting youtube.
+#[derive(Clone)]
+pub(crate) struct HttpConfig {
+    pub proxy: Option<Proxy>,
+    pub user_agent: String,
+    pub accept_language: String,
+    pub consent_cookie: bool,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            accept_language: "en-US,en;q=0.9".to_string(),
+            consent_cookie: true,
+            timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// headers sent with every request.
+    fn headers(&self) -> Result<HeaderMap, error::Error> {
+        let header = |name: HeaderName, value: &str| {
+            HeaderValue::from_str(value)
+                .map(|x| (name.clone(), x))
+                .map_err(|_| error::Error::InvalidHeader(format!("{}: {:?}", name, value)))
+        };
+        Ok([
+            header(USER_AGENT, &self.user_agent)?,
+            header(ACCEPT_LANGUAGE, &self.accept_language)?,
+        ]
+        .into_iter()
+        .collect())
+    }
+
+    /// builds a [`Client`] keeping cookies across requests.
+    pub(crate) fn client(&self) -> Result<Client, error::Error> {
+        let jar = Jar::default();
+        if self.consent_cookie {
+            if let Ok(url) = CONSENT_URL.parse::<Url>() {
+                jar.add_cookie_str(CONSENT_COOKIE, &url);
+            }
+        }
+        let mut builder = Client::builder()
+            .default_headers(self.headers()?)
+            .cookie_provider(Arc::new(jar));
+        if let Some(proxy) = self.proxy.clone() {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_client() {
+        let config = HttpConfig {
+            proxy: Some(Proxy::all("http://127.0.0.1:3128").unwrap()),
+            accept_language: "de-DE".to_string(),
+            ..HttpConfig::default()
+        };
+        assert!(config.client().is_ok());
+        let headers = config.headers().unwrap();
+        assert_eq!(headers[ACCEPT_LANGUAGE], "de-DE");
+        assert_eq!(headers[USER_AGENT], DEFAULT_USER_AGENT);
+        let config = HttpConfig {
+            accept_language: "de-DE\n".to_string(),
+            ..HttpConfig::default()
+        };
+        assert!(matches!(
+            config.client(),
+            Err(error::Error::InvalidHeader(_))
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::error;
+use crate::metadata::{Microformat, VideoDetails, VideoMetadata};
+use crate::parser::{Caption, Captions};
+use crate::VideoId;
+use serde::Deserialize;
+use serde_json::json;
+
+/// configuration of the innertube client used for requesting youtube's player api.
+/// Paths are requested on the watch host of the [`crate::Config`].
+pub(crate) struct InnertubeConfig {
+    pub player_path: &'static str,
+    pub browse_path: &'static str,
+    pub resolve_path: &'static str,
+    pub client_name: &'static str,
+    pub client_version: &'static str,
+    /// client used for browsing playlists, other clients get a different layout.
+    pub web_client_name: &'static str,
+    pub web_client_version: &'static str,
+}
+
+impl Default for InnertubeConfig {
+    fn default() -> Self {
+        Self {
+            player_path: "/youtubei/v1/player?prettyPrint=false",
+            browse_path: "/youtubei/v1/browse?prettyPrint=false",
+            resolve_path: "/youtubei/v1/navigation/resolve_url?prettyPrint=false",
+            client_name: "ANDROID",
+            client_version: "20.10.38",
+            web_client_name: "WEB",
+            web_client_version: "2.20250312.04.00",
+        }
+    }
+}
+
+impl InnertubeConfig {
+    /// json body of the player request for the video.
+    pub(crate) fn request_body(&self, video_id: &VideoId) -> String {
+        json!({
+            "context": {
+                "client": {
+                    "clientName": self.client_name,
+                    "clientVersion": self.client_version,
+                }
+            },
+            "videoId": video_id.as_str(),
+        })
+        .to_string()
+    }
+
+    fn web_context(&self) -> serde_json::Value {
+        json!({
+            "client": {
+                "clientName": self.web_client_name,
+                "clientVersion": self.web_client_version,
+            }
+        })
+    }
+
+    /// json body of the browse request for the first page of the browse id. Ex: "VL<playlist id>"
+    pub(crate) fn browse_body(&self, browse_id: &str) -> String {
+        json!({ "context": self.web_context(), "browseId": browse_id }).to_string()
+    }
+
+    /// json body of the browse request for the page of the continuation token.
+    pub(crate) fn continuation_body(&self, token: &str) -> String {
+        json!({ "context": self.web_context(), "continuation": token }).to_string()
+    }
+
+    /// json body of the request resolving the url into its browse endpoint.
+    pub(crate) fn resolve_body(&self, url: &str) -> String {
+        json!({ "context": self.web_context(), "url": url }).to_string()
+    }
+}
+
+#[derive(Deserialize)]
+struct PlayabilityStatus {
+    status: String,
+    reason: Option<String>,
+}
+
+impl PlayabilityStatus {
+    /// maps a status other than "OK" to the reason the video cannot be played.
+    fn check(&self) -> Result<(), error::Error> {
+        let reason = self.reason.clone().unwrap_or_else(|| self.status.clone());
+        let lowercase = reason.to_lowercase();
+        match self.status.as_str() {
+            "OK" => Ok(()),
+            _ if lowercase.contains("private") => Err(error::Error::VideoPrivate(reason)),
+            _ if lowercase.contains("not a bot") => Err(error::Error::TooManyRequests),
+            "AGE_CHECK_REQUIRED" | "AGE_VERIFICATION_REQUIRED" => {
+                Err(error::Error::AgeRestricted(reason))
+            }
+            "LOGIN_REQUIRED" if lowercase.contains("confirm your age") => {
+                Err(error::Error::AgeRestricted(reason))
+            }
+            _ => Err(error::Error::VideoUnavailable(reason)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PlayerCaptions {
+    #[serde(rename(deserialize = "playerCaptionsTracklistRenderer"))]
+    renderer: Captions,
+}
+
+/// Response of youtube's `/youtubei/v1/player` endpoint.
+#[derive(Deserialize)]
+pub(crate) struct PlayerResponse {
+    #[serde(rename(deserialize = "playabilityStatus"))]
+    playability_status: Option<PlayabilityStatus>,
+    captions: Option<PlayerCaptions>,
+    #[serde(rename(deserialize = "videoDetails"))]
+    video_details: Option<VideoDetails>,
+    microformat: Option<Microformat>,
+}
+
+impl PlayerResponse {
+    pub(crate) fn parse(text: &str) -> Result<Self, error::Error> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// parses the player response json embedded in the html right after the anchor.
+    pub(crate) fn from_html(html: &str, from: &str) -> Result<Self, error::Error> {
+        let start = html
+            .split_once(from)
+            .ok_or_else(|| error::Error::LayoutChanged(format!("Cannot parse html for: {}", from)))?
+            .1;
+        serde_json::Deserializer::from_str(start)
+            .into_iter::<Self>()
+            .next()
+            .ok_or_else(|| error::Error::LayoutChanged("Cannot find player response".into()))?
+            .map_err(|x| error::Error::LayoutChanged(format!("{}", x)))
+    }
+
+    /// fails with the reason the video cannot be played.
+    pub(crate) fn playable(&self) -> Result<(), error::Error> {
+        match &self.playability_status {
+            Some(status) => status.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// whether the response lists caption tracks for the video.
+    pub(crate) fn has_captions(&self) -> bool {
+        self.captions
+            .as_ref()
+            .is_some_and(|x| !x.renderer.caption_tracks.is_empty())
+    }
+
+    /// fails if the video is not playable or the response is not about the requested video.
+    fn check(&self, video_id: &VideoId) -> Result<(), error::Error> {
+        self.playable()?;
+        match &self.video_details {
+            Some(details) if details.video_id == video_id.as_str() => Ok(()),
+            _ => Err(error::Error::ParseError(format!(
+                "Cannot find video details for: {video_id}"
+            ))),
+        }
+    }
+
+    /// caption tracks of the video.
+    pub(crate) fn captions(self, video_id: &VideoId) -> Result<Vec<Caption>, error::Error> {
+        self.check(video_id)?;
+        Ok(self
+            .captions
+            .map(|x| x.renderer.caption_tracks)
+            .unwrap_or_default())
+    }
+
+    /// metadata of the video.
+    pub(crate) fn metadata(self, video_id: &VideoId) -> Result<VideoMetadata, error::Error> {
+        self.check(video_id)?;
+        let details = self.video_details.ok_or_else(|| {
+            error::Error::ParseError(format!("Cannot find video details for: {video_id}"))
+        })?;
+        Ok(VideoMetadata::new(details, self.microformat))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CaptionKind;
+
+    const PLAYER: &str = r#"{"responseContext":{},"playabilityStatus":{"status":"OK","playableInEmbed":true},"captions":{"playerCaptionsTracklistRenderer":{"captionTracks":[{"baseUrl":"https://www.youtube.com/api/timedtext?v=GJLlxj_dtq8&lang=en","name":{"runs":[{"text":"English"}]},"vssId":".en","languageCode":"en","isTranslatable":true},{"baseUrl":"https://www.youtube.com/api/timedtext?v=GJLlxj_dtq8&kind=asr&lang=en","name":{"runs":[{"text":"English (auto-generated)"}]},"vssId":"a.en","languageCode":"en","kind":"asr","isTranslatable":true}],"audioTracks":[{"captionTrackIndices":[0,1]}]}},"videoDetails":{"videoId":"GJLlxj_dtq8","title":"Microsoft Surface Go - Review"}}"#;
+
+    #[test]
+    fn test_player_captions() {
+        let video_id: VideoId = "GJLlxj_dtq8".parse().unwrap();
+        let captions = PlayerResponse::parse(PLAYER)
+            .unwrap()
+            .captions(&video_id)
+            .unwrap();
+        assert_eq!(captions.len(), 2);
+        assert_eq!(captions[0].name, "English");
+        assert_eq!(captions[1].kind, CaptionKind::Asr);
+    }
+
+    #[test]
+    fn test_player_metadata() {
+        let video_id: VideoId = "GJLlxj_dtq8".parse().unwrap();
+        let metadata = PlayerResponse::parse(PLAYER)
+            .unwrap()
+            .metadata(&video_id)
+            .unwrap();
+        assert_eq!(metadata.title, "Microsoft Surface Go - Review");
+        assert_eq!(metadata.publish_date, None);
+    }
+
+    #[test]
+    fn test_player_unplayable() {
+        let video_id: VideoId = "GJLlxj_dtq8".parse().unwrap();
+        let player = r#"{"playabilityStatus":{"status":"LOGIN_REQUIRED","reason":"Sign in to confirm your age"}}"#;
+        assert!(matches!(
+            PlayerResponse::parse(player).unwrap().captions(&video_id),
+            Err(error::Error::AgeRestricted(_))
+        ));
+        let player =
+            r#"{"playabilityStatus":{"status":"LOGIN_REQUIRED","reason":"This video is private"}}"#;
+        assert!(matches!(
+            PlayerResponse::parse(player).unwrap().captions(&video_id),
+            Err(error::Error::VideoPrivate(_))
+        ));
+        let other: VideoId = "aaaaaaaaaaa".parse().unwrap();
+        assert!(PlayerResponse::parse(PLAYER)
+            .unwrap()
+            .captions(&other)
+            .is_err());
+    }
+
+    #[test]
+    fn test_request_body() {
+        let video_id: VideoId = "GJLlxj_dtq8".parse().unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(&InnertubeConfig::default().request_body(&video_id)).unwrap();
+        assert_eq!(body["videoId"], "GJLlxj_dtq8");
+        assert_eq!(body["context"]["client"]["clientName"], "ANDROID");
+    }
+}
@@ -0,0 +1,222 @@
+use crate::error;
+use crate::LangCode;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// BCP-47 language tag of a caption track, with optional script and region subtags. Ex: "en", "pt-BR", "zh-Hant"
+///
+/// Subtags are normalized to their conventional case, "ZH_hant_tw" is parsed as "zh-Hant-TW".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageTag {
+    tag: String,
+    language: String,
+    script: Option<String>,
+    region: Option<String>,
+}
+
+/// deprecated language codes still sent by youtube, mapped to their current code. Ex: "iw" for hebrew
+const ALIASES: [(&str, &str); 4] = [("iw", "he"), ("in", "id"), ("ji", "yi"), ("jw", "jv")];
+
+/// current code of the language subtag.
+fn canonical(language: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == language)
+        .map_or(language, |(_, code)| code)
+}
+
+impl LanguageTag {
+    /// normalized tag. Ex: "zh-Hant-TW"
+    pub fn as_str(&self) -> &str {
+        &self.tag
+    }
+
+    /// primary language subtag. Ex: "zh"
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// script subtag if present. Ex: "Hant"
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    /// region subtag if present. Ex: "TW"
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// script of the tag, inferred from the region for languages written in several scripts.
+    fn likely_script(&self) -> Option<&str> {
+        if self.script.is_some() {
+            return self.script();
+        }
+        match (canonical(&self.language), self.region()) {
+            ("zh", Some("TW" | "HK" | "MO")) => Some("Hant"),
+            ("zh", _) => Some("Hans"),
+            ("sr", _) => Some("Cyrl"),
+            _ => None,
+        }
+    }
+
+    /// checks if a caption track's `languageCode` satisfies the tag.
+    ///
+    /// A bare language matches any of its variants, "en" matches "en-GB". A script or region restricts the
+    /// tracks to the same script, inferring it from the region when missing: "zh-Hant" matches "zh-TW"
+    /// and "zh-HK" but not "zh-CN" or "zh".
+    pub fn matches(&self, lang_code: &str) -> bool {
+        let Ok(track) = lang_code.parse::<LanguageTag>() else {
+            return false;
+        };
+        if canonical(&self.language) != canonical(&track.language) {
+            return false;
+        }
+        if self.script.is_none() && self.region.is_none() {
+            return true;
+        }
+        if let (Some(script), Some(track_script)) = (self.likely_script(), track.likely_script()) {
+            if script != track_script {
+                return false;
+            }
+        }
+        match (self.region(), track.region()) {
+            (Some(region), Some(track_region)) => region == track_region,
+            // a track tagged by script only, ex: "zh-Hant" for "zh-TW"
+            (Some(_), None) => track.script.is_some(),
+            (None, _) => true,
+        }
+    }
+
+    /// checks if the caption track's `languageCode` is the same tag, ignoring case and deprecated codes.
+    pub(crate) fn is_exactly(&self, lang_code: &str) -> bool {
+        lang_code
+            .parse::<LanguageTag>()
+            .is_ok_and(|x| x.without_alias() == self.without_alias())
+    }
+
+    fn without_alias(&self) -> String {
+        let rest = &self.tag[self.language.len()..];
+        format!("{}{}", canonical(&self.language), rest)
+    }
+}
+
+impl FromStr for LanguageTag {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || error::Error::InvalidLanguageTag(s.into());
+        let subtags = s.trim().split(['-', '_']).collect::<Vec<_>>();
+        if subtags
+            .iter()
+            .any(|x| x.is_empty() || x.len() > 8 || !x.chars().all(|c| c.is_ascii_alphanumeric()))
+        {
+            return Err(invalid());
+        }
+        let language = subtags[0].to_ascii_lowercase();
+        if !matches!(language.len(), 2 | 3 | 5..=8)
+            || !language.chars().all(|x| x.is_ascii_alphabetic())
+        {
+            return Err(invalid());
+        }
+        let (mut script, mut region) = (None, None);
+        let mut normalized = vec![language.clone()];
+        // script and region subtags come before any extension or private use singleton. Ex: "de-DE-u-co-phonebk"
+        let mut extension = false;
+        for subtag in &subtags[1..] {
+            let is_alpha = subtag.chars().all(|x| x.is_ascii_alphabetic());
+            let is_digit = subtag.chars().all(|x| x.is_ascii_digit());
+            extension |= subtag.len() == 1;
+            let subtag = if extension {
+                subtag.to_ascii_lowercase()
+            } else if subtag.len() == 4 && is_alpha && script.is_none() && region.is_none() {
+                let (first, rest) = subtag.split_at(1);
+                let subtag = first.to_ascii_uppercase() + &rest.to_ascii_lowercase();
+                script = Some(subtag.clone());
+                subtag
+            } else if ((subtag.len() == 2 && is_alpha) || (subtag.len() == 3 && is_digit))
+                && region.is_none()
+            {
+                let subtag = subtag.to_ascii_uppercase();
+                region = Some(subtag.clone());
+                subtag
+            } else {
+                subtag.to_ascii_lowercase()
+            };
+            normalized.push(subtag);
+        }
+        Ok(Self {
+            tag: normalized.join("-"),
+            language,
+            script,
+            region,
+        })
+    }
+}
+
+impl Display for LanguageTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.tag)
+    }
+}
+
+impl Default for LanguageTag {
+    fn default() -> Self {
+        LangCode::default().into()
+    }
+}
+
+impl From<LangCode> for LanguageTag {
+    fn from(value: LangCode) -> Self {
+        <&str>::from(value)
+            .parse()
+            .expect("language codes are valid tags")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tag(tag: &str) -> LanguageTag {
+        tag.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_language_tag() {
+        let zh = tag("ZH_hant_tw");
+        assert_eq!(zh.as_str(), "zh-Hant-TW");
+        assert_eq!(zh.language(), "zh");
+        assert_eq!(zh.script(), Some("Hant"));
+        assert_eq!(zh.region(), Some("TW"));
+        assert_eq!(tag("es-419").region(), Some("419"));
+        assert_eq!(tag("de-DE-u-co-phonebk").as_str(), "de-DE-u-co-phonebk");
+        assert_eq!(tag("fil").to_string(), "fil");
+        assert_eq!(LanguageTag::from(LangCode::ro).as_str(), "ro");
+        for invalid in ["", "e", "en-", "englishes", "1a", "en-GB!"] {
+            assert!(invalid.parse::<LanguageTag>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_language_tag_matches() {
+        assert!(tag("en").matches("en-GB"));
+        assert!(tag("en").matches("en"));
+        assert!(!tag("en-GB").matches("en"));
+        assert!(!tag("en-GB").matches("en-US"));
+        assert!(tag("pt-BR").matches("pt-BR"));
+        assert!(!tag("pt").matches("pl"));
+        assert!(tag("zh-Hant").matches("zh-TW"));
+        assert!(tag("zh-Hant").matches("zh-HK"));
+        assert!(tag("zh-Hant").matches("zh-Hant"));
+        assert!(!tag("zh-Hant").matches("zh-CN"));
+        assert!(!tag("zh-Hant").matches("zh"));
+        assert!(tag("zh-Hans").matches("zh"));
+        assert!(tag("zh-TW").matches("zh-Hant"));
+        assert!(!tag("zh-TW").matches("zh-Hans"));
+        assert!(tag("zh").matches("zh-Hant"));
+        assert!(!tag("sr-Latn").matches("sr"));
+        assert!(tag("he").matches("iw"));
+        assert!(tag("he").is_exactly("iw"));
+        assert!(!tag("en").is_exactly("en-GB"));
+    }
+}
@@ -12,12 +12,36 @@
 //! let transcript:Transcript = youtube_loader.transcript(link).await?;
 //! ```
 //!
+//! Enable the `blocking` feature for a synchronous api, see [`blocking`].
+//!
+#[cfg(any(test, feature = "blocking"))]
+pub mod blocking;
+mod chapter;
 mod config;
 mod utils;
 
 mod error;
+mod export;
+mod http;
+mod innertube;
+mod language;
+mod metadata;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+mod normalize;
 mod parser;
+mod playlist;
+mod search;
+mod sentence;
+mod video_id;
 mod youtube;
-pub use config::{LangCode, Config};
-pub use parser::{Transcript, TranscriptCore};
+pub use chapter::{Chapter, ChapterTranscript};
+pub use config::{Backend, CaptionPolicy, Config, LangCode, TranscriptFormat};
+pub use error::Error;
+pub use language::LanguageTag;
+pub use metadata::{Thumbnail, VideoMetadata};
+pub use parser::{Caption, CaptionKind, Transcript, TranscriptCore, TranscriptWord};
+pub use reqwest;
+pub use search::{Query, SearchHit};
+pub use video_id::VideoId;
 pub use youtube::{Youtube, YoutubeBuilder};
@@ -1,16 +1,31 @@
-use std::str::FromStr;
+use std::{
+    fmt::Display,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    time::Duration,
+};
 
 use clap::{
     builder::{self, IntoResettable},
-    Arg, Command,
+    Arg, ArgAction, ArgMatches, Command,
+};
+use futures::{stream, StreamExt};
+use serde_json::json;
+use youtube_transcript::{
+    reqwest::Proxy, Backend, CaptionPolicy, Error, LangCode, LanguageTag, Query, Transcript,
+    VideoId, Youtube, YoutubeBuilder,
 };
-use serde_json;
-use youtube_transcript::{LangCode, YoutubeBuilder};
 
 #[derive(Clone)]
 enum Format {
     Json,
     Text,
+    Srt,
+    Vtt,
+    Ttml,
+    Jsonl,
 }
 impl IntoResettable<builder::OsStr> for Format {
     fn into_resettable(self) -> builder::Resettable<builder::OsStr> {
@@ -19,22 +34,55 @@ impl IntoResettable<builder::OsStr> for Format {
     }
 }
 
-impl ToString for Format {
-    fn to_string(&self) -> String {
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Json => write!(f, "json"),
+            Format::Text => write!(f, "text"),
+            Format::Srt => write!(f, "srt"),
+            Format::Vtt => write!(f, "vtt"),
+            Format::Ttml => write!(f, "ttml"),
+            Format::Jsonl => write!(f, "jsonl"),
+        }
+    }
+}
+impl Format {
+    /// file extension of the transcript files written in the format.
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Text => "txt",
+            Format::Srt => "srt",
+            Format::Vtt => "vtt",
+            Format::Ttml => "ttml",
+            Format::Jsonl => "jsonl",
+        }
+    }
+
+    fn render(&self, video_id: Option<&VideoId>, transcript: Transcript) -> String {
         match self {
-            Format::Json => "json".to_string(),
-            Format::Text => "text".to_string(),
+            Format::Jsonl => json!({ "video_id": video_id, "transcript": transcript }).to_string(),
+            Format::Json => serde_json::to_string(&transcript).unwrap(),
+            Format::Text => String::from(transcript),
+            Format::Srt => transcript.to_srt(),
+            Format::Vtt => transcript.to_vtt(),
+            Format::Ttml => transcript.to_ttml(),
         }
     }
 }
+
 impl TryFrom<&str> for Format {
     type Error = String;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "json" => Ok(Format::Json),
             "text" => Ok(Format::Text),
+            "srt" => Ok(Format::Srt),
+            "vtt" => Ok(Format::Vtt),
+            "ttml" => Ok(Format::Ttml),
+            "jsonl" => Ok(Format::Jsonl),
             _ => Err(format!(
-                "Cannot find json / text as format definition. Recieved {}",
+                "Cannot find json / text / srt / vtt / ttml / jsonl as format definition. Recieved {}",
                 value
             )),
         }
@@ -45,47 +93,396 @@ fn format_parser(arg: &str) -> Result<Format, String> {
     arg.try_into()
 }
 
-fn format_lang_code(arg: &str) -> Result<LangCode, String> {
-    LangCode::from_str(arg).map_err(|err| format!("{err:?}"))
+fn format_lang_code(arg: &str) -> Result<LanguageTag, String> {
+    arg.parse().map_err(|err: Error| err.to_string())
 }
 
-#[tokio::main]
-async fn main() {
-    let app = Command::new("yts")
+fn format_caption_policy(arg: &str) -> Result<CaptionPolicy, String> {
+    CaptionPolicy::from_str(arg).map_err(|err| format!("{err:?}"))
+}
+
+fn format_backend(arg: &str) -> Result<Backend, String> {
+    Backend::from_str(arg).map_err(|err| format!("{err:?}"))
+}
+
+fn format_proxy(arg: &str) -> Result<Proxy, String> {
+    Proxy::all(arg).map_err(|err| format!("{err}"))
+}
+
+fn command() -> Command {
+    Command::new("yts")
         .arg(
             Arg::new("format")
-                .help("ouput format")
+                .global(true)
+                .help(
+                    "ouput format: json / text / srt / vtt / ttml / jsonl, one json line per video",
+                )
                 .long("format")
                 .value_parser(builder::ValueParser::new(format_parser))
                 .default_value(Format::Text),
         )
         .arg(
             Arg::new("lang_code")
-                .help("language code")
+                .global(true)
+                .help("BCP-47 language tag, ex: en / pt-BR / zh-Hant, repeat for an ordered list of fallback languages")
                 .long("lang-code")
+                .action(ArgAction::Append)
                 .value_parser(builder::ValueParser::new(format_lang_code))
                 .default_value(<&'static str>::from(LangCode::default())),
         )
+        .arg(
+            Arg::new("caption_policy")
+                .global(true)
+                .help("prefer-manual / prefer-asr / manual-only captions")
+                .long("caption-policy")
+                .value_parser(builder::ValueParser::new(format_caption_policy))
+                .default_value(<&'static str>::from(CaptionPolicy::default())),
+        )
+        .arg(
+            Arg::new("translate_to")
+                .global(true)
+                .help(
+                    "language code to translate captions into when no track of the language exists",
+                )
+                .long("translate-to")
+                .value_parser(builder::ValueParser::new(format_lang_code)),
+        )
+        .arg(
+            Arg::new("raw")
+                .global(true)
+                .help("keep caption text as sent by youtube without normalizing")
+                .long("raw")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("strip_markers")
+                .global(true)
+                .help("strip annotations like [Music] and [Applause] from captions")
+                .long("strip-markers")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("backend")
+                .global(true)
+                .help("html / innertube source of caption tracks")
+                .long("backend")
+                .value_parser(builder::ValueParser::new(format_backend))
+                .default_value(<&'static str>::from(Backend::default())),
+        )
+        .arg(
+            Arg::new("proxy")
+                .global(true)
+                .help("proxy url for all requests. Ex: socks5://127.0.0.1:1080")
+                .long("proxy")
+                .value_parser(builder::ValueParser::new(format_proxy)),
+        )
+        .arg(
+            Arg::new("timeout")
+                .global(true)
+                .help("timeout of every request in seconds")
+                .long("timeout")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("30"),
+        )
+        .arg(
+            Arg::new("out_dir")
+                .global(true)
+                .help("directory to write one transcript file per video id into")
+                .long("out-dir")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("concurrency")
+                .global(true)
+                .help("number of videos fetched at the same time")
+                .long("concurrency")
+                .value_parser(clap::value_parser!(u16).range(1..))
+                .default_value("4"),
+        )
+        .arg(
+            Arg::new("input")
+                .help("file with one link per line, - for stdin")
+                .long("input")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
         .arg(Arg::new("link").help("Youtube-link"))
-        .get_matches();
-    let format = app.get_one::<Format>("format").unwrap_or(&Format::Json);
-    let link = app
-        .get_one::<String>("link")
-        .expect("Youtube Link not provided");
-    let transcript = YoutubeBuilder::default()
-        .lang_code(
-            app.get_one::<LangCode>("lang_code")
+        .subcommand(
+            Command::new("playlist")
+                .about("writes one transcript file per video of a playlist or channel")
+                .arg(
+                    Arg::new("url")
+                        .help("playlist link / id, or channel link / handle / id")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("finds where the query is spoken in the video, with links to the moments")
+                .arg(Arg::new("link").help("Youtube-link").required(true))
+                .arg(
+                    Arg::new("query")
+                        .help("case-insensitive phrase to search for")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("regex")
+                        .help("treat the query as a regular expression")
+                        .long("regex")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("parse")
+                .about("parses saved responses without network calls, lists the caption tracks of a watch page when no captions are given")
+                .arg(
+                    Arg::new("html")
+                        .help("saved html of the watch page")
+                        .long("html")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required_unless_present("captions"),
+                )
+                .arg(
+                    Arg::new("captions")
+                        .help("saved timedtext response in xml / srv3 / json3 format")
+                        .long("captions")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+}
+
+/// builds [`Youtube`] from the options shared by every command.
+fn youtube(app: &ArgMatches) -> Youtube {
+    let mut youtube_builder = YoutubeBuilder::default()
+        .normalize(!app.get_flag("raw"))
+        .strip_markers(app.get_flag("strip_markers"))
+        .backend(
+            app.get_one::<Backend>("backend")
                 .copied()
-                .unwrap_or(LangCode::default()),
+                .unwrap_or_default(),
+        );
+    if let Some(timeout) = app.get_one::<u64>("timeout") {
+        youtube_builder = youtube_builder.timeout(Some(Duration::from_secs(*timeout)));
+    }
+    if let Some(proxy) = app.get_one::<Proxy>("proxy") {
+        youtube_builder = youtube_builder.proxy(proxy.clone());
+    }
+    if let Some(lang_code) = app.get_one::<LanguageTag>("translate_to") {
+        youtube_builder = youtube_builder.translate_to(lang_code.clone());
+    }
+    youtube_builder
+        .languages(
+            app.get_many::<LanguageTag>("lang_code")
+                .map(|x| x.cloned().collect::<Vec<_>>())
+                .unwrap_or_default(),
+            app.get_one::<CaptionPolicy>("caption_policy")
+                .copied()
+                .unwrap_or_default(),
         )
         .build()
-        .transcript(link)
+}
+
+/// links listed one per line in the file or stdin, skipping blank lines and `#` comments.
+fn read_links(path: &Path) -> io::Result<Vec<String>> {
+    let lines = if path == Path::new("-") {
+        io::stdin().lock().lines().collect::<io::Result<Vec<_>>>()?
+    } else {
+        io::BufReader::new(std::fs::File::open(path)?)
+            .lines()
+            .collect::<io::Result<Vec<_>>>()?
+    };
+    Ok(lines
+        .into_iter()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .collect())
+}
+
+async fn fetch(youtube: &Youtube, link: &str) -> Result<(VideoId, Transcript), Error> {
+    let video_id = link.parse::<VideoId>()?;
+    let transcript = youtube.transcript(video_id.as_str()).await?;
+    Ok((video_id, transcript))
+}
+
+/// fetches the transcripts of the links with bounded concurrency, continuing past failures.
+/// Transcripts are written as `<video id>.<extension>` into the out dir, or else printed in the order of the links.
+async fn batch(
+    app: &ArgMatches,
+    youtube: &Youtube,
+    links: Vec<String>,
+    out_dir: Option<&Path>,
+) -> ExitCode {
+    let format = app.get_one::<Format>("format").unwrap_or(&Format::Json);
+    let concurrency = app.get_one::<u16>("concurrency").copied().unwrap_or(1);
+    if let Some(out_dir) = out_dir {
+        if let Err(err) = std::fs::create_dir_all(out_dir) {
+            eprintln!("{}: {}", out_dir.display(), err);
+            return ExitCode::FAILURE;
+        }
+    }
+    let total = links.len();
+    let mut results = stream::iter(links)
+        .map(|link| async move {
+            let result = fetch(youtube, &link).await.map_err(|x| x.to_string());
+            (link, result)
+        })
+        .buffered(concurrency.into());
+    let mut failures = Vec::new();
+    while let Some((link, result)) = results.next().await {
+        let written = result.and_then(|(video_id, transcript)| {
+            let data = format.render(Some(&video_id), transcript);
+            match out_dir {
+                Some(out_dir) => {
+                    let path = out_dir.join(format!("{}.{}", video_id, format.extension()));
+                    std::fs::write(path, data).map_err(|x| x.to_string())
+                }
+                None => {
+                    println!("{}", data);
+                    Ok(())
+                }
+            }
+        });
+        if let Err(err) = written {
+            if let (Format::Jsonl, None) = (format, out_dir) {
+                println!("{}", json!({ "link": link, "error": err }));
+            }
+            failures.push((link, err));
+        }
+    }
+    if total > 1 || !failures.is_empty() {
+        eprintln!(
+            "fetched {} of {} transcripts",
+            total - failures.len(),
+            total
+        );
+    }
+    for (link, err) in &failures {
+        eprintln!("{}: {}", link, err);
+    }
+    if failures.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// fetches the transcript of every video of the playlist or channel, into the current directory by default.
+async fn playlist(app: &ArgMatches) -> ExitCode {
+    let youtube = youtube(app);
+    let url = app.get_one::<String>("url").unwrap();
+    let video_ids = match youtube.playlist_video_ids(url).await {
+        Err(Error::InvalidPlaylistId(_)) => youtube.channel_uploads(url).await,
+        x => x,
+    };
+    let links = match video_ids {
+        Ok(video_ids) => video_ids.iter().map(|x| x.to_string()).collect(),
+        Err(err) => {
+            eprintln!("{}: {}", url, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let out_dir = app
+        .get_one::<PathBuf>("out_dir")
+        .map_or(Path::new("."), |x| x.as_path());
+    batch(app, &youtube, links, Some(out_dir)).await
+}
+
+/// prints every hit of the query with the watch url starting at it.
+async fn search(app: &ArgMatches) -> Result<(), String> {
+    let format = app.get_one::<Format>("format").unwrap_or(&Format::Json);
+    let link = app.get_one::<String>("link").unwrap();
+    let query = app.get_one::<String>("query").unwrap();
+    let query = if app.get_flag("regex") {
+        Query::regex(query).map_err(|x| x.to_string())?
+    } else {
+        Query::phrase(query)
+    };
+    let video_id = link.parse::<VideoId>().map_err(|x| x.to_string())?;
+    let transcript = youtube(app)
+        .transcript(video_id.as_str())
         .await
-        .unwrap();
+        .map_err(|x| x.to_string())?;
+    let hits = transcript
+        .search(query)
+        .into_iter()
+        .map(|hit| (hit.url(&video_id), hit));
+    match format {
+        Format::Json => {
+            let hits = hits
+                .map(|(url, hit)| json!({ "url": url, "hit": hit }))
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::Value::from(hits))
+        }
+        Format::Jsonl => {
+            hits.for_each(|(url, hit)| println!("{}", json!({ "url": url, "hit": hit })))
+        }
+        _ => hits.for_each(|(url, hit)| println!("{}\t{}", url, hit.context)),
+    }
+    Ok(())
+}
 
-    let data = match format {
-        Format::Json => serde_json::to_string(&transcript).unwrap(),
-        Format::Text => String::from(transcript),
+/// prints the transcript of the saved captions, or else the caption tracks of the saved watch page.
+fn parse(app: &ArgMatches) -> Result<(), String> {
+    let youtube = youtube(app);
+    let format = app.get_one::<Format>("format").unwrap_or(&Format::Json);
+    let read = |path: &PathBuf| {
+        std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))
     };
-    println!("{}", data);
+    let html = app.get_one::<PathBuf>("html").map(read).transpose()?;
+    let Some(captions) = app.get_one::<PathBuf>("captions") else {
+        let tracks = youtube
+            .caption_tracks_from_text(&html.unwrap_or_default())
+            .map_err(|x| x.to_string())?;
+        println!("{}", serde_json::to_string(&tracks).unwrap());
+        return Ok(());
+    };
+    let video_id = html
+        .and_then(|x| youtube.metadata_from_text(&x).ok())
+        .and_then(|x| x.video_id.parse::<VideoId>().ok());
+    let transcript = youtube
+        .transcript_from_timedtext(&read(captions)?)
+        .map_err(|x| x.to_string())?;
+    println!("{}", format.render(video_id.as_ref(), transcript));
+    Ok(())
+}
+
+/// reports the error of a command on stderr.
+fn exit_code(result: Result<(), String>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let app = command().get_matches();
+    match app.subcommand() {
+        Some(("playlist", playlist_app)) => return playlist(playlist_app).await,
+        Some(("search", search_app)) => return exit_code(search(search_app).await),
+        Some(("parse", parse_app)) => return exit_code(parse(parse_app)),
+        _ => {}
+    }
+    let mut links = app
+        .get_one::<String>("link")
+        .cloned()
+        .into_iter()
+        .collect::<Vec<_>>();
+    if let Some(input) = app.get_one::<PathBuf>("input") {
+        match read_links(input) {
+            Ok(input_links) => links.extend(input_links),
+            Err(err) => {
+                eprintln!("{}: {}", input.display(), err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if links.is_empty() {
+        eprintln!("Youtube Link not provided");
+        return ExitCode::FAILURE;
+    }
+    let out_dir = app.get_one::<PathBuf>("out_dir").map(|x| x.as_path());
+    batch(&app, &youtube(&app), links, out_dir).await
 }
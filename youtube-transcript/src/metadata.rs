@@ -0,0 +1,187 @@
+use crate::error;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::Duration;
+
+/// deserializes numbers sent by youtube as strings. Ex: "lengthSeconds": "312"
+fn string_number<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+/// Thumbnail of a youtube video.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Thumbnail {
+    /// url of the thumbnail image.
+    pub url: String,
+    /// width of the image in pixels.
+    #[serde(default)]
+    pub width: u32,
+    /// height of the image in pixels.
+    #[serde(default)]
+    pub height: u32,
+}
+
+#[derive(Deserialize, Default)]
+struct Thumbnails {
+    #[serde(default)]
+    thumbnails: Vec<Thumbnail>,
+}
+
+/// `videoDetails` of youtube's player response.
+#[derive(Deserialize)]
+pub(crate) struct VideoDetails {
+    #[serde(rename(deserialize = "videoId"))]
+    pub video_id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    author: String,
+    #[serde(rename(deserialize = "channelId"), default)]
+    channel_id: String,
+    #[serde(
+        rename(deserialize = "lengthSeconds"),
+        deserialize_with = "string_number",
+        default
+    )]
+    length_seconds: u64,
+    #[serde(rename(deserialize = "shortDescription"), default)]
+    short_description: String,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(
+        rename(deserialize = "viewCount"),
+        deserialize_with = "string_number",
+        default
+    )]
+    view_count: u64,
+    #[serde(default)]
+    thumbnail: Thumbnails,
+}
+
+#[derive(Deserialize)]
+struct PlayerMicroformatRenderer {
+    #[serde(rename(deserialize = "publishDate"))]
+    publish_date: Option<String>,
+}
+
+/// `microformat` of youtube's player response, only sent to web clients.
+#[derive(Deserialize)]
+pub(crate) struct Microformat {
+    #[serde(rename(deserialize = "playerMicroformatRenderer"))]
+    renderer: Option<PlayerMicroformatRenderer>,
+}
+
+/// Player response embedded in the watch page as `ytInitialPlayerResponse`.
+#[derive(Deserialize)]
+pub(crate) struct PlayerMetadata {
+    #[serde(rename(deserialize = "videoDetails"))]
+    video_details: VideoDetails,
+    microformat: Option<Microformat>,
+}
+
+impl PlayerMetadata {
+    /// parses the player response json starting right after the anchor in the html.
+    pub(crate) fn from_html(html: &str, from: &str) -> Result<Self, error::Error> {
+        let start = html
+            .split_once(from)
+            .ok_or_else(|| error::Error::LayoutChanged(format!("Cannot parse html for: {}", from)))?
+            .1;
+        serde_json::Deserializer::from_str(start)
+            .into_iter::<Self>()
+            .next()
+            .ok_or_else(|| error::Error::LayoutChanged("Cannot find player response".into()))?
+            .map_err(|x| error::Error::ParseError(format!("{}", x)))
+    }
+}
+
+impl From<PlayerMetadata> for VideoMetadata {
+    fn from(value: PlayerMetadata) -> Self {
+        VideoMetadata::new(value.video_details, value.microformat)
+    }
+}
+
+/// Metadata of a youtube video, parsed from the same response as the caption tracks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VideoMetadata {
+    /// video id. Ex: "GJLlxj_dtq8"
+    pub video_id: String,
+    /// title of the video.
+    pub title: String,
+    /// name of the channel that uploaded the video.
+    pub channel_name: String,
+    /// id of the channel that uploaded the video. Ex: "UCVYamHliCI9rw1tHR1xbkfw"
+    pub channel_id: String,
+    /// duration of the video.
+    pub duration: Duration,
+    /// date the video was published on. Ex: "2018-08-02"
+    pub publish_date: Option<String>,
+    /// description of the video.
+    pub description: String,
+    /// keywords / tags of the video.
+    pub keywords: Vec<String>,
+    /// number of views of the video.
+    pub view_count: u64,
+    /// thumbnails of the video ordered as sent by youtube, usually from smallest to largest.
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+impl VideoMetadata {
+    pub(crate) fn new(details: VideoDetails, microformat: Option<Microformat>) -> Self {
+        Self {
+            video_id: details.video_id,
+            title: details.title,
+            channel_name: details.author,
+            channel_id: details.channel_id,
+            duration: Duration::from_secs(details.length_seconds),
+            publish_date: microformat
+                .and_then(|x| x.renderer)
+                .and_then(|x| x.publish_date),
+            description: details.short_description,
+            keywords: details.keywords,
+            view_count: details.view_count,
+            thumbnails: details.thumbnail.thumbnails,
+        }
+    }
+
+    /// thumbnail having the largest area.
+    pub fn best_thumbnail(&self) -> Option<&Thumbnail> {
+        self.thumbnails
+            .iter()
+            .max_by_key(|x| u64::from(x.width) * u64::from(x.height))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HTML: &str = r#"<script nonce="x">var ytInitialPlayerResponse = {"responseContext":{},"playabilityStatus":{"status":"OK"},"videoDetails":{"videoId":"GJLlxj_dtq8","title":"Microsoft Surface Go - Review","lengthSeconds":"312","keywords":["surface go","review"],"channelId":"UCVYamHliCI9rw1tHR1xbkfw","shortDescription":"Surface Go review.\n0:00 Intro","thumbnail":{"thumbnails":[{"url":"https://i.ytimg.com/vi/GJLlxj_dtq8/default.jpg","width":120,"height":90},{"url":"https://i.ytimg.com/vi/GJLlxj_dtq8/maxresdefault.jpg","width":1920,"height":1080},{"url":"https://i.ytimg.com/vi/GJLlxj_dtq8/hqdefault.jpg","width":480,"height":360}]},"viewCount":"1933436","author":"Dave2D","isPrivate":false},"microformat":{"playerMicroformatRenderer":{"publishDate":"2018-08-02","uploadDate":"2018-08-02"}}};var meta = document.createElement('meta');</script>"#;
+
+    #[test]
+    fn test_metadata_from_html() {
+        let metadata: VideoMetadata = PlayerMetadata::from_html(HTML, "ytInitialPlayerResponse = ")
+            .unwrap()
+            .into();
+        assert_eq!(metadata.video_id, "GJLlxj_dtq8");
+        assert_eq!(metadata.title, "Microsoft Surface Go - Review");
+        assert_eq!(metadata.channel_name, "Dave2D");
+        assert_eq!(metadata.channel_id, "UCVYamHliCI9rw1tHR1xbkfw");
+        assert_eq!(metadata.duration, Duration::from_secs(312));
+        assert_eq!(metadata.publish_date.as_deref(), Some("2018-08-02"));
+        assert_eq!(metadata.keywords, ["surface go", "review"]);
+        assert_eq!(metadata.view_count, 1_933_436);
+        assert_eq!(metadata.thumbnails.len(), 3);
+        assert_eq!(
+            metadata.best_thumbnail().unwrap().url,
+            "https://i.ytimg.com/vi/GJLlxj_dtq8/maxresdefault.jpg"
+        );
+    }
+
+    #[test]
+    fn test_metadata_missing() {
+        assert!(PlayerMetadata::from_html("<html></html>", "ytInitialPlayerResponse = ").is_err());
+    }
+}
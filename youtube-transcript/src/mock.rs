@@ -0,0 +1,232 @@
+//! Local http server replaying youtube responses from fixtures, so that fetches can be tested without network.
+//!
+//! Fixtures are read from a directory holding one sub directory per video id:
+//! - `<video id>/watch.html`: watch page served at `/watch?v=<video id>`
+//! - `<video id>/timedtext.xml`: captions served at `/api/timedtext?v=<video id>`, also as `timedtext.json3`
+//!   and `timedtext.srv3` for the `fmt` parameter
+//! - `<video id>/player.json`: innertube response served at `/youtubei/v1/player`
+//!
+//! The fixtures shipped in `fixtures/` are hand-written, not captured from youtube: the watch page only embeds a
+//! player response, and `player.json` is the same player response without its `responseContext`.
+//! Their caption urls carry expired signatures, so they only exercise the parsing and the request flow, not youtube's
+//! current layout. `record_fixtures` overwrites them with real captures when youtube is reachable:
+//! `cargo test -p youtube-transcript record_fixtures -- --ignored`
+use crate::YoutubeBuilder;
+use reqwest::Url;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// directory of the hand-written fixtures shipped with the crate.
+pub const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+/// Local http server serving the fixtures, stopped when dropped.
+pub struct MockServer {
+    url: String,
+    handle: JoinHandle<()>,
+    /// paths of the requests served so far.
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// serves the fixtures directory on a random local port.
+    pub async fn start(fixtures: impl Into<PathBuf>) -> io::Result<Self> {
+        let fixtures = fixtures.into();
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let served = requests.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let fixtures = fixtures.clone();
+                let served = served.clone();
+                tokio::spawn(async move {
+                    let _ = serve(stream, &fixtures, &served).await;
+                });
+            }
+        });
+        Ok(Self {
+            url,
+            handle,
+            requests,
+        })
+    }
+
+    /// serves the fixtures shipped with the crate.
+    pub async fn fixtures() -> io::Result<Self> {
+        Self::start(FIXTURES).await
+    }
+
+    /// scheme and host of the server. Ex: "http://127.0.0.1:40123"
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// number of requests received for the path. Ex: "/youtubei/v1/player"
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests
+            .lock()
+            .map_or(0, |x| x.iter().filter(|x| *x == path).count())
+    }
+
+    /// [`YoutubeBuilder`] sending every request to the server.
+    pub fn builder(&self) -> YoutubeBuilder {
+        YoutubeBuilder::default()
+            .watch_host(self.url.as_str())
+            .timedtext_host(self.url.as_str())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// reads a single request and replies with the fixture for it, closing the connection afterwards.
+async fn serve(
+    mut stream: TcpStream,
+    fixtures: &Path,
+    requests: &Mutex<Vec<String>>,
+) -> io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    let head_len = loop {
+        let n = stream.read(&mut buffer).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..n]);
+        if let Some(position) = request.windows(4).position(|x| x == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&request[..head_len]).to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|x| x.split_once(':'))
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or_default();
+    while request.len() < head_len + content_length {
+        let n = stream.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..n]);
+    }
+    let body = String::from_utf8_lossy(&request[head_len..]).to_string();
+    let target = head.split_whitespace().nth(1).unwrap_or("/");
+    if let Ok(mut requests) = requests.lock() {
+        requests.push(target.split('?').next().unwrap_or(target).to_string());
+    }
+    let response = match fixture(fixtures, target, &body) {
+        Some(path) => tokio::fs::read(path).await.ok(),
+        None => None,
+    };
+    let (status, content) = match response {
+        Some(content) => ("200 OK", content),
+        None => ("404 Not Found", Vec::new()),
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&content).await?;
+    stream.shutdown().await
+}
+
+/// path of the fixture answering the request target.
+fn fixture(fixtures: &Path, target: &str, body: &str) -> Option<PathBuf> {
+    let url = Url::parse(&format!("http://localhost{target}")).ok()?;
+    let query = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let (video_id, file) = match url.path() {
+        "/watch" => (query("v")?, "watch.html".to_string()),
+        "/api/timedtext" => {
+            let fmt = query("fmt").unwrap_or_else(|| "xml".into());
+            (query("v")?, format!("timedtext.{fmt}"))
+        }
+        "/youtubei/v1/player" => {
+            let body: serde_json::Value = serde_json::from_str(body).ok()?;
+            (body["videoId"].as_str()?.to_string(), "player.json".into())
+        }
+        _ => return None,
+    };
+    // video ids never contain path separators, anything else is not a fixture
+    if !video_id
+        .chars()
+        .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+    {
+        return None;
+    }
+    Some(fixtures.join(video_id).join(file))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_server() {
+        let server = MockServer::fixtures().await.unwrap();
+        let client = reqwest::Client::new();
+        let watch = client
+            .get(format!("{}/watch?v=GJLlxj_dtq8", server.url()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(watch.status(), 200);
+        assert!(watch
+            .text()
+            .await
+            .unwrap()
+            .contains("ytInitialPlayerResponse"));
+        let missing = client
+            .get(format!("{}/watch?v=aaaaaaaaaaa", server.url()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+    }
+
+    #[tokio::test]
+    #[ignore = "Records fixtures from youtube"]
+    async fn record_fixtures() {
+        let youtube = YoutubeBuilder::default().build();
+        let video_id: crate::VideoId = "GJLlxj_dtq8".parse().unwrap();
+        let watch = youtube.watch_page(&video_id).await.unwrap();
+        let captions = youtube.caption_tracks_from_text(&watch).unwrap();
+        let caption = captions.iter().find(|x| x.lang_code == "en").unwrap();
+        let timedtext = reqwest::get(&caption.base_url)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let innertube = crate::innertube::InnertubeConfig::default();
+        let player = reqwest::Client::new()
+            .post(format!("https://www.youtube.com{}", innertube.player_path))
+            .header("Content-Type", "application/json")
+            .body(innertube.request_body(&video_id))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let dir = Path::new(FIXTURES).join(video_id.as_str());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("watch.html"), watch).unwrap();
+        std::fs::write(dir.join("timedtext.xml"), timedtext).unwrap();
+        std::fs::write(dir.join("player.json"), player).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use crate::parser::{Transcript, TranscriptCore, TranscriptWord};
+
+/// maximum passes of entity decoding, captions are often escaped twice. Ex: "&amp;#39;"
+const MAX_DECODE_PASSES: usize = 3;
+
+/// decodes a single html entity without the surrounding `&` and `;`. Ex: "#39" or "amp"
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse::<u32>().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+fn decode_entities_once(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        decoded.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 8)
+            .and_then(|end| Some((decode_entity(&rest[1..=end])?, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 2..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// decodes html entities until the text is stable. Ex: "how&amp;#39;s" to "how's"
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut decoded = text.to_string();
+    for _ in 0..MAX_DECODE_PASSES {
+        let next = decode_entities_once(&decoded);
+        if next == decoded {
+            break;
+        }
+        decoded = next;
+    }
+    decoded
+}
+
+/// removes bracketed annotations. Ex: "[Music]" or "[Applause]"
+fn strip_markers(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        match rest[start..].find(']') {
+            Some(end) => {
+                stripped.push_str(&rest[..start]);
+                stripped.push(' ');
+                rest = &rest[start + end + 1..];
+            }
+            None => break,
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// decodes entities, optionally strips markers and collapses whitespace and newlines into single spaces.
+pub(crate) fn normalize_text(text: &str, markers: bool) -> String {
+    let mut text = decode_entities(text);
+    if markers {
+        text = strip_markers(&text);
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Transcript {
+    /// normalizes the text of every caption by decoding html entities and collapsing whitespace.
+    /// `strip_markers` removes annotations like "[Music]" and captions left empty are dropped.
+    pub fn normalize(self, strip_markers: bool) -> Self {
+        let transcripts = self
+            .transcripts
+            .into_iter()
+            .filter_map(|x| {
+                let text = normalize_text(&x.text, strip_markers);
+                if text.is_empty() {
+                    return None;
+                }
+                let words = x
+                    .words
+                    .into_iter()
+                    .map(|word| TranscriptWord {
+                        text: decode_entities(&word.text),
+                        start: word.start,
+                    })
+                    .collect();
+                Some(TranscriptCore { text, words, ..x })
+            })
+            .collect();
+        Self { transcripts }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("how&amp;#39;s"), "how's");
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#x41; &quot;c&quot;"),
+            "a <b> A \"c\""
+        );
+        assert_eq!(
+            decode_entities("fish & chips &unknown;"),
+            "fish & chips &unknown;"
+        );
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(
+            normalize_text("  we&#39;re\nback   [Music] ", false),
+            "we're back [Music]"
+        );
+        assert_eq!(
+            normalize_text("[Applause] we&#39;re\nback [Music]", true),
+            "we're back"
+        );
+    }
+
+    #[test]
+    fn test_normalize_transcript() {
+        let core = |text: &str| TranscriptCore {
+            text: text.into(),
+            start: Duration::from_secs(0),
+            duration: Duration::from_secs(1),
+            words: Vec::new(),
+        };
+        let transcript = Transcript {
+            transcripts: vec![core("[Music]"), core("how&#39;s\nit going")],
+        }
+        .normalize(true);
+        assert_eq!(transcript.transcripts, vec![core("how's it going")]);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::error;
+use crate::video_id::{parse_url, youtube_host};
+use crate::VideoId;
+use serde_json::Value;
+
+/// prefix of channel ids. Ex: "UCVYamHliCI9rw1tHR1xbkfw"
+const CHANNEL_PREFIX: &str = "UC";
+/// prefix of the playlist holding every upload of a channel.
+const UPLOADS_PREFIX: &str = "UU";
+/// length of every youtube channel id.
+const CHANNEL_ID_LEN: usize = 24;
+
+fn is_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+}
+
+fn is_channel_id(id: &str) -> bool {
+    id.len() == CHANNEL_ID_LEN && id.starts_with(CHANNEL_PREFIX) && is_id(id)
+}
+
+/// playlist id from a bare id or the `list` parameter of a link. Ex: "PLFgquLnL59alCl_2TQvOiD5Vgm1hCaGSI"
+pub(crate) fn playlist_id(url: &str) -> Result<String, error::Error> {
+    let url = url.trim();
+    // bare playlist ids are longer than video ids, channel ids are left to `channel_id`
+    if url.len() > 11 && is_id(url) && !is_channel_id(url) {
+        return Ok(url.into());
+    }
+    parse_url(url)
+        .filter(|x| youtube_host(x).is_some())
+        .and_then(|x| {
+            x.query_pairs()
+                .find(|(key, _)| key == "list")
+                .map(|(_, value)| value.into_owned())
+        })
+        .filter(|x| is_id(x))
+        .ok_or_else(|| error::Error::InvalidPlaylistId(url.into()))
+}
+
+/// channel id from a bare id or a `/channel/<id>` link, handles like `/@name` need to be resolved by youtube.
+pub(crate) fn channel_id(url: &str) -> Option<String> {
+    let url = url.trim();
+    if is_channel_id(url) {
+        return Some(url.into());
+    }
+    let url = parse_url(url).filter(|x| youtube_host(x).is_some())?;
+    let mut segments = url.path_segments()?.filter(|x| !x.is_empty());
+    match (segments.next(), segments.next()) {
+        (Some("channel"), Some(id)) if is_channel_id(id) => Some(id.into()),
+        _ => None,
+    }
+}
+
+/// link of a channel that youtube can resolve to its channel id. Ex: "https://www.youtube.com/@name"
+pub(crate) fn channel_url(url: &str) -> String {
+    let url = url.trim();
+    if url.starts_with('@') {
+        format!("https://www.youtube.com/{url}")
+    } else if url.contains("://") {
+        url.into()
+    } else {
+        format!("https://{url}")
+    }
+}
+
+/// id of the playlist holding every upload of the channel.
+pub(crate) fn uploads_playlist_id(channel_id: &str) -> String {
+    let id = channel_id
+        .strip_prefix(CHANNEL_PREFIX)
+        .unwrap_or(channel_id);
+    format!("{UPLOADS_PREFIX}{id}")
+}
+
+/// channel id from the response of youtube's `/youtubei/v1/navigation/resolve_url` endpoint.
+pub(crate) fn resolved_channel_id(text: &str) -> Result<Option<String>, error::Error> {
+    let value: Value = serde_json::from_str(text)?;
+    Ok(value["endpoint"]["browseEndpoint"]["browseId"]
+        .as_str()
+        .filter(|x| x.starts_with(CHANNEL_PREFIX))
+        .map(String::from))
+}
+
+/// Page of videos from youtube's `/youtubei/v1/browse` endpoint.
+#[derive(Debug, Default)]
+pub(crate) struct BrowsePage {
+    pub video_ids: Vec<VideoId>,
+    /// token requesting the next page, absent on the last page.
+    pub continuation: Option<String>,
+}
+
+impl BrowsePage {
+    /// collects the playlist videos and the continuation token wherever they are nested in the response.
+    pub(crate) fn parse(text: &str) -> Result<Self, error::Error> {
+        let value: Value = serde_json::from_str(text)?;
+        let mut page = Self::default();
+        page.collect(&value);
+        Ok(page)
+    }
+
+    fn collect(&mut self, value: &Value) {
+        match value {
+            Value::Object(map) => {
+                if let Some(id) = map
+                    .get("playlistVideoRenderer")
+                    .and_then(|x| x["videoId"].as_str())
+                    .and_then(|x| x.parse().ok())
+                {
+                    self.video_ids.push(id);
+                }
+                if let Some(token) = map
+                    .get("continuationCommand")
+                    .and_then(|x| x["token"].as_str())
+                {
+                    self.continuation = Some(token.into());
+                }
+                map.values().for_each(|x| self.collect(x));
+            }
+            Value::Array(values) => values.iter().for_each(|x| self.collect(x)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BROWSE: &str = r#"{"contents":{"twoColumnBrowseResultsRenderer":{"tabs":[{"tabRenderer":{"content":{"sectionListRenderer":{"contents":[{"itemSectionRenderer":{"contents":[{"playlistVideoListRenderer":{"contents":[{"playlistVideoRenderer":{"videoId":"GJLlxj_dtq8","title":{"runs":[{"text":"Microsoft Surface Go - Review"}]}}},{"playlistVideoRenderer":{"videoId":"RcYjXbSJBN8","title":{"runs":[{"text":"Talk"}]}}},{"continuationItemRenderer":{"continuationEndpoint":{"continuationCommand":{"token":"4qmFsgJhEiRWTFBM","request":"CONTINUATION_REQUEST_TYPE_BROWSE"}}}}]}}]}}]}}}}]}}}"#;
+    const CONTINUATION: &str = r#"{"onResponseReceivedActions":[{"appendContinuationItemsAction":{"continuationItems":[{"playlistVideoRenderer":{"videoId":"dQw4w9WgXcQ"}}],"targetId":"browse-feedVLPL"}}]}"#;
+
+    #[test]
+    fn test_browse_page() {
+        let page = BrowsePage::parse(BROWSE).unwrap();
+        assert_eq!(
+            page.video_ids,
+            vec![
+                "GJLlxj_dtq8".parse::<VideoId>().unwrap(),
+                "RcYjXbSJBN8".parse().unwrap()
+            ]
+        );
+        assert_eq!(page.continuation.as_deref(), Some("4qmFsgJhEiRWTFBM"));
+        let page = BrowsePage::parse(CONTINUATION).unwrap();
+        assert_eq!(page.video_ids.len(), 1);
+        assert_eq!(page.continuation, None);
+    }
+
+    #[test]
+    fn test_playlist_id() {
+        let id = "PLFgquLnL59alCl_2TQvOiD5Vgm1hCaGSI";
+        let links = [
+            id.to_string(),
+            format!("https://www.youtube.com/playlist?list={id}"),
+            format!("https://www.youtube.com/watch?v=GJLlxj_dtq8&list={id}&index=2"),
+            format!("m.youtube.com/playlist?list={id}"),
+        ];
+        for link in links {
+            assert_eq!(playlist_id(&link).unwrap(), id, "{link}");
+        }
+        assert!(playlist_id("GJLlxj_dtq8").is_err());
+        assert!(matches!(
+            playlist_id("UCVYamHliCI9rw1tHR1xbkfw"),
+            Err(error::Error::InvalidPlaylistId(_))
+        ));
+        assert!(playlist_id("https://example.com/playlist?list=PLFgquLnL59a").is_err());
+    }
+
+    #[test]
+    fn test_channel_id() {
+        let id = "UCVYamHliCI9rw1tHR1xbkfw";
+        assert_eq!(channel_id(id).as_deref(), Some(id));
+        assert_eq!(
+            channel_id(&format!("https://www.youtube.com/channel/{id}/videos")).as_deref(),
+            Some(id)
+        );
+        assert_eq!(channel_id("https://www.youtube.com/@Dave2D"), None);
+        assert_eq!(channel_url("@Dave2D"), "https://www.youtube.com/@Dave2D");
+        assert_eq!(uploads_playlist_id(id), "UUVYamHliCI9rw1tHR1xbkfw");
+        let resolved = r#"{"endpoint":{"browseEndpoint":{"browseId":"UCVYamHliCI9rw1tHR1xbkfw"}}}"#;
+        assert_eq!(resolved_channel_id(resolved).unwrap().as_deref(), Some(id));
+    }
+}
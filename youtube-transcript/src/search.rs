@@ -0,0 +1,150 @@
+use crate::error;
+use crate::parser::Transcript;
+use crate::VideoId;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Case-insensitive query searched for in a [`Transcript`].
+#[derive(Debug, Clone)]
+pub struct Query(Regex);
+
+impl Query {
+    /// matches the words of the phrase in order, separated by any whitespace. Ex: "surface go"
+    pub fn phrase(phrase: &str) -> Self {
+        let pattern = phrase
+            .split_whitespace()
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(r"\s+");
+        Self::regex(&pattern).expect("escaped phrase is a valid regex")
+    }
+
+    /// matches the regular expression. Ex: r"\$\d+"
+    pub fn regex(pattern: &str) -> Result<Self, error::Error> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(Self)
+            .map_err(|x| error::Error::InvalidQuery(x.to_string()))
+    }
+}
+
+impl From<&str> for Query {
+    fn from(value: &str) -> Self {
+        Self::phrase(value)
+    }
+}
+
+/// Span of a [`Transcript`] matching a [`Query`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    /// matched text.
+    pub text: String,
+    /// text of the captions the match spans.
+    pub context: String,
+    /// start of the first caption of the match.
+    pub start: Duration,
+    /// end of the last caption of the match.
+    pub end: Duration,
+    /// indices of the captions in [`Transcript::transcripts`] the match spans.
+    pub cues: Range<usize>,
+}
+
+impl SearchHit {
+    /// watch url of the video starting at the hit. Ex: "https://www.youtube.com/watch?v=GJLlxj_dtq8&t=37s"
+    pub fn url(&self, video_id: &VideoId) -> String {
+        format!("{}&t={}s", video_id.watch_url(), self.start.as_secs())
+    }
+}
+
+impl Transcript {
+    /// finds every match of the query, matching across caption boundaries.
+    pub fn search(&self, query: impl Into<Query>) -> Vec<SearchHit> {
+        let query = query.into();
+        // captions are joined by a space, each caption's byte range in the joined text is kept.
+        let mut text = String::new();
+        let mut ranges = Vec::with_capacity(self.transcripts.len());
+        for transcript in &self.transcripts {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let start = text.len();
+            text.push_str(transcript.text.trim());
+            ranges.push(start..text.len());
+        }
+        let cue = |offset: usize| ranges.partition_point(|x| x.end <= offset);
+        query
+            .0
+            .find_iter(&text)
+            .filter(|x| !x.as_str().trim().is_empty())
+            .map(|x| {
+                let cues = cue(x.start())..cue(x.end() - 1) + 1;
+                let first = &self.transcripts[cues.start];
+                let last = &self.transcripts[cues.end - 1];
+                SearchHit {
+                    text: x.as_str().into(),
+                    context: text[ranges[cues.start].start..ranges[cues.end - 1].end].into(),
+                    start: first.start,
+                    end: last.start + last.duration,
+                    cues,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::TranscriptCore;
+
+    fn transcript() -> Transcript {
+        let cue = |text: &str, start: u64| TranscriptCore {
+            text: text.into(),
+            start: Duration::from_secs(start),
+            duration: Duration::from_secs(3),
+            words: Vec::new(),
+        };
+        Transcript {
+            transcripts: vec![
+                cue("This is a Microsoft Surface", 1),
+                cue("go and it starts at $400", 5),
+                cue("the keyboard is a hundred bucks", 37),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_search_phrase() {
+        let transcript = transcript();
+        let hits = transcript.search("microsoft surface GO");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, "Microsoft Surface go");
+        assert_eq!(hits[0].cues, 0..2);
+        assert_eq!(hits[0].start, Duration::from_secs(1));
+        assert_eq!(hits[0].end, Duration::from_secs(8));
+        assert_eq!(
+            hits[0].context,
+            "This is a Microsoft Surface go and it starts at $400"
+        );
+        let hits = transcript.search("keyboard");
+        let video_id: VideoId = "GJLlxj_dtq8".parse().unwrap();
+        assert_eq!(
+            hits[0].url(&video_id),
+            "https://www.youtube.com/watch?v=GJLlxj_dtq8&t=37s"
+        );
+        assert!(transcript.search("macbook").is_empty());
+    }
+
+    #[test]
+    fn test_search_regex() {
+        let transcript = transcript();
+        let hits = transcript.search(Query::regex(r"\$\d+|hundred").unwrap());
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].text, "$400");
+        assert_eq!(hits[1].cues, 2..3);
+        assert!(Query::regex("(").is_err());
+    }
+}
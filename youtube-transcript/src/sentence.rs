@@ -0,0 +1,291 @@
+use crate::parser::{Transcript, TranscriptCore, TranscriptWord};
+use std::time::Duration;
+
+/// characters ending a sentence when followed by whitespace or the end of a caption.
+const SENTENCE_TERMINATORS: [char; 7] = ['.', '!', '?', '…', '。', '！', '？'];
+/// full-width terminators end a sentence whatever follows, chinese and japanese put no space after them.
+const FULL_WIDTH_TERMINATORS: [char; 3] = ['。', '！', '？'];
+/// silence after which a sentence is ended even without punctuation, auto-generated captions have none.
+const SENTENCE_GAP: Duration = Duration::from_secs(2);
+
+/// Text being merged from several captions along with its start and end in the whole video.
+struct Unit {
+    text: String,
+    start: Duration,
+    end: Duration,
+    words: Vec<TranscriptWord>,
+}
+
+impl Unit {
+    fn push(&mut self, text: &str, end: Duration, words: Vec<TranscriptWord>) {
+        if !self.text.is_empty() {
+            self.text.push(' ');
+        }
+        self.text.push_str(text);
+        self.end = end;
+        self.words.extend(words);
+    }
+}
+
+impl From<Unit> for TranscriptCore {
+    fn from(value: Unit) -> Self {
+        Self {
+            text: value.text,
+            start: value.start,
+            duration: value.end.saturating_sub(value.start),
+            words: value.words,
+        }
+    }
+}
+
+/// Accumulates pieces of captions into units and flushes them as [`TranscriptCore`].
+#[derive(Default)]
+struct Merger {
+    current: Option<Unit>,
+    merged: Vec<TranscriptCore>,
+}
+
+impl Merger {
+    fn push(&mut self, text: &str, start: Duration, end: Duration, words: Vec<TranscriptWord>) {
+        match self.current.as_mut() {
+            Some(unit) => unit.push(text, end, words),
+            None => {
+                self.current = Some(Unit {
+                    text: text.to_string(),
+                    start,
+                    end,
+                    words,
+                })
+            }
+        }
+    }
+
+    fn end(&self) -> Option<Duration> {
+        self.current.as_ref().map(|x| x.end)
+    }
+
+    fn flush(&mut self) {
+        if let Some(unit) = self.current.take() {
+            self.merged.push(unit.into());
+        }
+    }
+
+    fn finish(mut self) -> Transcript {
+        self.flush();
+        Transcript {
+            transcripts: self.merged,
+        }
+    }
+}
+
+/// Piece of a caption split at sentence terminators, with its timing interpolated from the caption.
+struct Piece<'a> {
+    text: &'a str,
+    start: Duration,
+    end: Duration,
+    terminal: bool,
+}
+
+/// splits the caption at sentence terminators, interpolating the timing of each piece by its position in the text.
+// `Option::is_none_or` needs rust 1.82
+#[allow(clippy::unnecessary_map_or)]
+fn split_caption(caption: &TranscriptCore) -> Vec<Piece<'_>> {
+    let text = caption.text.as_str();
+    let n_chars = text.chars().count().max(1) as u32;
+    let at = |index: usize| {
+        caption.start + caption.duration * text[..index].chars().count() as u32 / n_chars
+    };
+    let mut pieces = Vec::new();
+    let mut piece_start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if !SENTENCE_TERMINATORS.contains(&c) {
+            continue;
+        }
+        let next = chars.peek().map(|&(_, x)| x);
+        if next.is_some_and(|x| SENTENCE_TERMINATORS.contains(&x)) {
+            continue;
+        }
+        if FULL_WIDTH_TERMINATORS.contains(&c) || next.map_or(true, char::is_whitespace) {
+            let piece_end = index + c.len_utf8();
+            pieces.push(Piece {
+                text: text[piece_start..piece_end].trim(),
+                start: at(piece_start),
+                end: at(piece_end),
+                terminal: true,
+            });
+            piece_start = piece_end;
+        }
+    }
+    if !text[piece_start..].trim().is_empty() {
+        pieces.push(Piece {
+            text: text[piece_start..].trim(),
+            start: at(piece_start),
+            end: caption.start + caption.duration,
+            terminal: false,
+        });
+    }
+    pieces
+}
+
+impl Transcript {
+    /// regroups captions into sentences, ending a sentence at terminal punctuation or at a silence longer than two seconds.
+    /// Each sentence keeps the start and end of the text it is made of.
+    pub fn sentences(&self) -> Transcript {
+        let mut merger = Merger::default();
+        for caption in &self.transcripts {
+            if merger
+                .end()
+                .is_some_and(|end| caption.start.saturating_sub(end) > SENTENCE_GAP)
+            {
+                merger.flush();
+            }
+            let pieces = split_caption(caption);
+            let n_pieces = pieces.len();
+            for (i, piece) in pieces.into_iter().enumerate() {
+                let words = caption
+                    .words
+                    .iter()
+                    .filter(|x| x.start >= piece.start || i == 0)
+                    .filter(|x| x.start < piece.end || i + 1 == n_pieces)
+                    .cloned()
+                    .collect();
+                merger.push(piece.text, piece.start, piece.end, words);
+                if piece.terminal {
+                    merger.flush();
+                }
+            }
+        }
+        merger.finish()
+    }
+
+    /// regroups sentences into paragraphs, starting a new paragraph after a silence longer than `max_gap`.
+    pub fn paragraphs(&self, max_gap: Duration) -> Transcript {
+        let mut merger = Merger::default();
+        for sentence in self.sentences() {
+            if merger
+                .end()
+                .is_some_and(|end| sentence.start.saturating_sub(end) > max_gap)
+            {
+                merger.flush();
+            }
+            merger.push(
+                &sentence.text,
+                sentence.start,
+                sentence.start + sentence.duration,
+                sentence.words,
+            );
+        }
+        merger.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn core(text: &str, start: u64, duration: u64) -> TranscriptCore {
+        TranscriptCore {
+            text: text.into(),
+            start: Duration::from_millis(start),
+            duration: Duration::from_millis(duration),
+            words: Vec::new(),
+        }
+    }
+
+    fn transcript() -> Transcript {
+        Transcript {
+            transcripts: vec![
+                core(
+                    "so this unit here, is that mid tier model the",
+                    30_750,
+                    3_300,
+                ),
+                core("$550 unit and I", 34_780, 2_000),
+                core("Really like it. Ok, let's go around.", 37_420, 3_600),
+                core("This thing build quality is great", 41_020, 1_600),
+                core("it has a magnesium enclosure", 50_000, 2_000),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_sentences() {
+        let sentences = transcript().sentences();
+        let texts = sentences
+            .transcripts
+            .iter()
+            .map(|x| x.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                "so this unit here, is that mid tier model the $550 unit and I Really like it.",
+                "Ok, let's go around.",
+                "This thing build quality is great",
+                "it has a magnesium enclosure"
+            ]
+        );
+        let first = &sentences.transcripts[0];
+        assert_eq!(first.start, Duration::from_millis(30_750));
+        assert_eq!(first.start + first.duration, Duration::from_millis(38_920));
+        let second = &sentences.transcripts[1];
+        assert_eq!(second.start, Duration::from_millis(38_920));
+        assert_eq!(
+            second.start + second.duration,
+            Duration::from_millis(41_020)
+        );
+    }
+
+    #[test]
+    fn test_sentences_decimal() {
+        let transcript = Transcript {
+            transcripts: vec![core("it costs 5.5 dollars... really?! yes", 0, 3_600)],
+        };
+        let texts = transcript
+            .sentences()
+            .into_iter()
+            .map(|x| x.text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["it costs 5.5 dollars...", "really?!", "yes"]);
+    }
+
+    #[test]
+    fn test_sentences_cjk() {
+        let transcript = Transcript {
+            transcripts: vec![
+                core("今日は晴れです。明日は雨ですか？", 0, 3_000),
+                core("はい！そうです", 3_000, 2_000),
+            ],
+        };
+        let sentences = transcript.sentences();
+        let texts = sentences
+            .transcripts
+            .iter()
+            .map(|x| x.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            ["今日は晴れです。", "明日は雨ですか？", "はい！", "そうです"]
+        );
+        assert_eq!(sentences.transcripts[1].start, Duration::from_millis(1_500));
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let paragraphs = transcript().paragraphs(Duration::from_secs(5));
+        assert_eq!(paragraphs.transcripts.len(), 2);
+        assert_eq!(
+            paragraphs.transcripts[0].start,
+            Duration::from_millis(30_750)
+        );
+        assert_eq!(
+            paragraphs.transcripts[0].start + paragraphs.transcripts[0].duration,
+            Duration::from_millis(42_620)
+        );
+        assert_eq!(
+            paragraphs.transcripts[1].text,
+            "it has a magnesium enclosure"
+        );
+    }
+}
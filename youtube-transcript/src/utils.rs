@@ -21,3 +21,30 @@ pub(crate) fn to_human_readable(duration: &Duration) -> String {
         ),
     }
 }
+
+/// formats the duration as `hh:mm:ss<separator>mmm`. Ex: "00:01:02,500" for srt
+pub(crate) fn to_timestamp(duration: &Duration, separator: char) -> String {
+    let millis = duration.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        (millis % 3_600_000) / 60_000,
+        (millis % 60_000) / 1000,
+        separator,
+        millis % 1000
+    )
+}
+
+/// escapes the characters having special meaning in xml / html text content.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,153 @@
+use crate::error;
+use reqwest::Url;
+use serde::Serialize;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// length of every youtube video id.
+const VIDEO_ID_LEN: usize = 11;
+/// hosts serving youtube videos, subdomains like www / m / music are allowed.
+const HOSTS: [&str; 3] = ["youtube.com", "youtube-nocookie.com", "youtu.be"];
+/// path prefixes followed by the video id. Ex: "/shorts/<id>"
+const PATH_PREFIXES: [&str; 5] = ["shorts", "embed", "live", "v", "e"];
+
+/// Validated youtube video id. Ex: "GJLlxj_dtq8"
+///
+/// Parses bare ids and links like `youtube.com/watch?v=<id>`, `youtu.be/<id>`, `/shorts/<id>`, `/embed/<id>`,
+/// `/live/<id>` on any youtube host including `m.youtube.com`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct VideoId(String);
+
+impl VideoId {
+    /// video id as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// canonical watch url of the video. Ex: "https://www.youtube.com/watch?v=GJLlxj_dtq8"
+    pub fn watch_url(&self) -> String {
+        format!("https://www.youtube.com/watch?v={}", self.0)
+    }
+
+    fn is_valid(id: &str) -> bool {
+        id.len() == VIDEO_ID_LEN
+            && id
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+    }
+
+    fn from_url(url: &Url) -> Option<Self> {
+        let host = youtube_host(url)?;
+        let mut segments = url.path_segments()?.filter(|x| !x.is_empty());
+        let id = match (host, segments.next()) {
+            ("youtu.be", Some(id)) => id.to_string(),
+            (_, Some("watch")) | (_, None) => url
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, value)| value.into_owned())?,
+            (_, Some(prefix)) if PATH_PREFIXES.contains(&prefix) => segments.next()?.to_string(),
+            _ => return None,
+        };
+        Self::is_valid(&id).then_some(Self(id))
+    }
+}
+
+/// youtube host of the url without subdomains. Ex: "youtube.com" for "m.youtube.com"
+pub(crate) fn youtube_host(url: &Url) -> Option<&'static str> {
+    let host = url.host_str()?;
+    HOSTS
+        .into_iter()
+        .find(|x| host == *x || host.ends_with(&format!(".{x}")))
+}
+
+/// parses links with or without the scheme. Ex: "youtu.be/GJLlxj_dtq8"
+pub(crate) fn parse_url(s: &str) -> Option<Url> {
+    if s.contains("://") {
+        Url::parse(s).ok()
+    } else {
+        Url::parse(&format!("https://{s}")).ok()
+    }
+}
+
+impl FromStr for VideoId {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if Self::is_valid(s) {
+            return Ok(Self(s.to_string()));
+        }
+        parse_url(s)
+            .and_then(|x| Self::from_url(&x))
+            .ok_or_else(|| error::Error::InvalidVideoId(s.into()))
+    }
+}
+
+impl TryFrom<&str> for VideoId {
+    type Error = error::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for VideoId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_video_id_links() {
+        let links = [
+            "GJLlxj_dtq8",
+            "https://www.youtube.com/watch?v=GJLlxj_dtq8",
+            "https://www.youtube.com/watch?feature=share&v=GJLlxj_dtq8&t=10s",
+            "http://youtube.com/watch?v=GJLlxj_dtq8",
+            "www.youtube.com/watch?v=GJLlxj_dtq8",
+            "https://m.youtube.com/watch?v=GJLlxj_dtq8",
+            "https://music.youtube.com/watch?v=GJLlxj_dtq8&list=RDAMVM",
+            "https://youtu.be/GJLlxj_dtq8",
+            "https://youtu.be/GJLlxj_dtq8?si=abc&t=42",
+            "https://www.youtube.com/shorts/GJLlxj_dtq8",
+            "https://www.youtube.com/embed/GJLlxj_dtq8?start=10",
+            "https://www.youtube-nocookie.com/embed/GJLlxj_dtq8",
+            "https://www.youtube.com/live/GJLlxj_dtq8?feature=shared",
+            "https://www.youtube.com/v/GJLlxj_dtq8",
+            " https://www.youtube.com/watch?v=GJLlxj_dtq8\n",
+        ];
+        for link in links {
+            let video_id: VideoId = link.parse().unwrap();
+            assert_eq!(video_id.as_str(), "GJLlxj_dtq8", "{link}");
+        }
+    }
+
+    #[test]
+    fn test_video_id_invalid() {
+        let links = [
+            "",
+            "GJLlxj_dtq",
+            "GJLlxj_dtq8!",
+            "https://www.youtube.com/watch?v=short",
+            "https://www.youtube.com/channel/UCxyz",
+            "https://example.com/watch?v=GJLlxj_dtq8",
+            "https://notyoutube.com/watch?v=GJLlxj_dtq8",
+        ];
+        for link in links {
+            assert!(link.parse::<VideoId>().is_err(), "{link}");
+        }
+    }
+
+    #[test]
+    fn test_video_id_watch_url() {
+        let video_id: VideoId = "https://youtu.be/GJLlxj_dtq8".parse().unwrap();
+        assert_eq!(
+            video_id.watch_url(),
+            "https://www.youtube.com/watch?v=GJLlxj_dtq8"
+        );
+    }
+}
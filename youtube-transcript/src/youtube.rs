@@ -1,63 +1,407 @@
-use crate::config::Config;
-use crate::parser::{HTMLParser, Transcript, TranscriptParser};
-use crate::LangCode;
-use reqwest::Client;
-use roxmltree::Document;
-use std::error::Error;
-
-/// Youtube container that holds the [`Config`].
+use crate::chapter::Chapter;
+use crate::config::{Backend, CaptionPolicy, Config, TranscriptFormat};
+use crate::innertube::PlayerResponse;
+use crate::metadata::{PlayerMetadata, VideoMetadata};
+use crate::parser::{caption_url, Caption, HTMLParser, Transcript, TranscriptParser};
+use crate::playlist::{
+    channel_id, channel_url, playlist_id, resolved_channel_id, uploads_playlist_id, BrowsePage,
+};
+use crate::{error, LanguageTag, VideoId};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use reqwest::{Client, Proxy, RequestBuilder, Url};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Youtube container that holds the [`Config`] and the http [`Client`] reused across requests.
 pub struct Youtube {
     config: Config,
+    client: Client,
 }
 
 impl Youtube {
-    /// extracts [`Transcript`] from the video link provided.
-    pub async fn transcript<'a>(&self, url: &'a str) -> Result<Transcript, Box<dyn Error>> {
-        let client = Client::default();
-        let response = client.get(url).send().await?;
-        let text = response.text().await?;
-        self.transcript_from_text(&text).await
+    /// extracts [`Transcript`] from the video link or video id provided.
+    pub async fn transcript(&self, url: &str) -> Result<Transcript, error::Error> {
+        let video_id = VideoId::from_str(url)?;
+        let captions = self.fetch_captions(&video_id).await?;
+        self.transcript_from_captions(&captions).await
+    }
+    /// lists every caption track available for the video link or video id provided.
+    pub async fn caption_tracks(&self, url: &str) -> Result<Vec<Caption>, error::Error> {
+        let video_id = VideoId::from_str(url)?;
+        self.fetch_captions(&video_id).await
+    }
+
+    /// fetches the raw html of the watch page of the video.
+    pub async fn watch_page(&self, video_id: &VideoId) -> Result<String, error::Error> {
+        let url = format!("{}/watch?v={}", self.config.watch_host, video_id);
+        self.text(self.client.get(url)).await
+    }
+
+    /// lists every caption track available in the youtube raw html text provided.
+    pub fn caption_tracks_from_text(&self, text: &str) -> Result<Vec<Caption>, error::Error> {
+        match text.captions(self.config.parser.from, self.config.parser.to) {
+            Ok(captions) => Ok(captions),
+            Err(err) => {
+                // tell apart unplayable videos and videos without captions from a layout change.
+                let response =
+                    match PlayerResponse::from_html(text, self.config.parser.player_response) {
+                        Ok(response) => response,
+                        Err(_) => return Err(err),
+                    };
+                response.playable()?;
+                if response.has_captions() {
+                    return Err(err);
+                }
+                Err(error::Error::CaptionsDisabled)
+            }
+        }
+    }
+
+    /// lists the videos of the playlist link or playlist id provided, in playlist order.
+    pub async fn playlist_video_ids(&self, url: &str) -> Result<Vec<VideoId>, error::Error> {
+        self.browse_playlist(&playlist_id(url)?).await
+    }
+
+    /// lists the videos uploaded by the channel link, handle or channel id provided, newest first.
+    pub async fn channel_uploads(&self, url: &str) -> Result<Vec<VideoId>, error::Error> {
+        let channel_id = match channel_id(url) {
+            Some(channel_id) => channel_id,
+            None => {
+                let body = self.config.innertube.resolve_body(&channel_url(url));
+                let text = self
+                    .innertube(self.config.innertube.resolve_path, body)
+                    .await?;
+                resolved_channel_id(&text)?
+                    .ok_or_else(|| error::Error::ChannelNotFound(url.into()))?
+            }
+        };
+        self.browse_playlist(&uploads_playlist_id(&channel_id))
+            .await
+    }
+
+    /// extracts [`VideoMetadata`] of the video link or video id provided.
+    pub async fn metadata(&self, url: &str) -> Result<VideoMetadata, error::Error> {
+        let video_id = VideoId::from_str(url)?;
+        if self.config.backend == Backend::Innertube {
+            match self
+                .innertube_player(&video_id)
+                .await
+                .and_then(|x| x.metadata(&video_id))
+            {
+                Ok(metadata) => return Ok(metadata),
+                Err(err) => fall_back(&video_id, err)?,
+            }
+        }
+        let text = self.watch_page(&video_id).await?;
+        self.metadata_from_text(&text)
     }
+
+    /// extracts [`VideoMetadata`] from the youtube raw html text provided.
+    pub fn metadata_from_text(&self, text: &str) -> Result<VideoMetadata, error::Error> {
+        Ok(PlayerMetadata::from_html(text, self.config.parser.player_response)?.into())
+    }
+
+    /// extracts the [`Chapter`]s of the video link or video id provided.
+    pub async fn chapters(&self, url: &str) -> Result<Vec<Chapter>, error::Error> {
+        let video_id = VideoId::from_str(url)?;
+        let text = self.watch_page(&video_id).await?;
+        self.chapters_from_text(&text)
+    }
+
+    /// extracts the [`Chapter`]s from the youtube raw html text provided. Uses the chapter markers of the
+    /// player, falling back to the timestamps in the description.
+    pub fn chapters_from_text(&self, text: &str) -> Result<Vec<Chapter>, error::Error> {
+        let metadata = self.metadata_from_text(text).ok();
+        let duration = metadata
+            .as_ref()
+            .map(|x| x.duration)
+            .filter(|x| !x.is_zero());
+        let chapters = Chapter::from_html(text, self.config.parser.chapter, duration)?;
+        if !chapters.is_empty() {
+            return Ok(chapters);
+        }
+        Ok(metadata
+            .map(|x| Chapter::from_description(&x.description, duration))
+            .unwrap_or_default())
+    }
+
     /// extracts [`Transcript`] from the youtube raw html text provided.
-    pub async fn transcript_from_text(&self, text: &str) -> Result<Transcript, Box<dyn Error>> {
-        let client = Client::default();
-        let c = text.caption(
-            self.config.parser.from,
-            self.config.parser.to,
-            self.config.lang_code.into(),
+    pub async fn transcript_from_text(&self, text: &str) -> Result<Transcript, error::Error> {
+        let captions = self.caption_tracks_from_text(text)?;
+        self.transcript_from_captions(&captions).await
+    }
+
+    /// extracts [`Transcript`] from a saved timedtext response without any network call.
+    /// The [`TranscriptFormat`] is detected from the text.
+    pub fn transcript_from_timedtext(&self, text: &str) -> Result<Transcript, error::Error> {
+        let format = TranscriptFormat::detect(text).ok_or_else(|| {
+            error::Error::ParseError("Cannot detect the format of the timedtext".into())
+        })?;
+        let t = TranscriptParser::parse_text(text, format)?;
+        Ok(self.normalize(t))
+    }
+
+    /// fetches caption tracks from the configured [`Backend`], falling back to the html scraper.
+    async fn fetch_captions(&self, video_id: &VideoId) -> Result<Vec<Caption>, error::Error> {
+        if self.config.backend == Backend::Innertube {
+            match self.innertube_captions(video_id).await {
+                Ok(captions) => return Ok(captions),
+                Err(err) => fall_back(video_id, err)?,
+            }
+        }
+        let text = self.watch_page(video_id).await?;
+        self.caption_tracks_from_text(&text)
+    }
+
+    /// fetches caption tracks from youtube's innertube player api.
+    async fn innertube_captions(&self, video_id: &VideoId) -> Result<Vec<Caption>, error::Error> {
+        self.innertube_player(video_id).await?.captions(video_id)
+    }
+
+    /// requests youtube's innertube player api.
+    async fn innertube_player(&self, video_id: &VideoId) -> Result<PlayerResponse, error::Error> {
+        let body = self.config.innertube.request_body(video_id);
+        PlayerResponse::parse(
+            &self
+                .innertube(self.config.innertube.player_path, body)
+                .await?,
+        )
+    }
+
+    /// posts the json body to the innertube endpoint at the path of the watch host.
+    async fn innertube(&self, path: &str, body: String) -> Result<String, error::Error> {
+        let request = self
+            .client
+            .post(format!("{}{}", self.config.watch_host, path))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
+        self.text(request).await
+    }
+
+    /// browses every page of the playlist, following continuation tokens until the last page.
+    async fn browse_playlist(&self, playlist_id: &str) -> Result<Vec<VideoId>, error::Error> {
+        let innertube = &self.config.innertube;
+        let mut body = innertube.browse_body(&format!("VL{playlist_id}"));
+        let mut video_ids = Vec::new();
+        let mut seen = HashSet::new();
+        let mut tokens = HashSet::new();
+        loop {
+            let page = BrowsePage::parse(&self.innertube(innertube.browse_path, body).await?)?;
+            video_ids.extend(
+                page.video_ids
+                    .into_iter()
+                    .filter(|x| seen.insert(x.clone())),
+            );
+            match page.continuation {
+                // a repeated token would page forever
+                Some(token) if tokens.insert(token.clone()) => {
+                    body = innertube.continuation_body(&token)
+                }
+                _ => return Ok(video_ids),
+            }
+        }
+    }
+
+    /// selects a caption track from the tracks as per the [`Config`] and extracts its [`Transcript`].
+    async fn transcript_from_captions(
+        &self,
+        captions: &[Caption],
+    ) -> Result<Transcript, error::Error> {
+        let url = caption_url(
+            captions,
+            &self.config.lang_codes,
+            self.config.policy,
+            self.config.translate_to.as_ref(),
         )?;
-        let response = client.get(c.base_url).send().await?;
-        let trans_resp = response.text().await?;
-        let doc = Document::parse(&trans_resp)?;
-        let t = TranscriptParser::parse(&doc)?;
-        Ok(t)
+        let url = self.timedtext_url(&url)?;
+        let url = match self.config.format.fmt() {
+            Some(fmt) => format!("{url}&fmt={fmt}"),
+            None => url,
+        };
+        let trans_resp = self.text(self.client.get(url)).await?;
+        let t = TranscriptParser::parse_text(&trans_resp, self.config.format)?;
+        Ok(self.normalize(t))
+    }
+
+    /// moves the caption url onto the configured timedtext host.
+    fn timedtext_url(&self, url: &str) -> Result<String, error::Error> {
+        let url = Url::parse(url).map_err(|x| error::Error::ParseError(format!("{}", x)))?;
+        let mut timedtext_url = format!("{}{}", self.config.timedtext_host, url.path());
+        if let Some(query) = url.query() {
+            timedtext_url = format!("{timedtext_url}?{query}");
+        }
+        Ok(timedtext_url)
+    }
+
+    /// normalizes the caption text if enabled in the [`Config`].
+    fn normalize(&self, transcript: Transcript) -> Transcript {
+        if self.config.normalize {
+            return transcript.normalize(self.config.strip_markers);
+        }
+        transcript
+    }
+
+    /// sends the request and reads the body of a successful response.
+    async fn text(&self, request: RequestBuilder) -> Result<String, error::Error> {
+        let response = request.send().await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(error::Error::TooManyRequests);
+        }
+        Ok(response.error_for_status()?.text().await?)
+    }
+}
+
+/// returns the innertube error unless the html scraper may succeed where the player api failed.
+/// Playability errors and rate limiting would fail the same way on the watch page.
+fn fall_back(video_id: &VideoId, err: error::Error) -> Result<(), error::Error> {
+    match err {
+        error::Error::ParseError(_) | error::Error::LayoutChanged(_) | error::Error::Network(_) => {
+            log::warn!(
+                "Innertube player failed for {}, falling back to the watch page: {}",
+                video_id,
+                err
+            );
+            Ok(())
+        }
+        err => Err(err),
     }
 }
 
 /// Builder struct for building [`Youtube`]
 pub struct YoutubeBuilder {
     config: Config,
+    client: Option<Client>,
 }
 
-impl YoutubeBuilder {
+impl Default for YoutubeBuilder {
     /// creates [`YoutubeBuilder`] with default [`Config`] values.
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             config: Config::default(),
+            client: None,
         }
     }
+}
+
+impl YoutubeBuilder {
+    /// set language code, either a [`crate::LangCode`] or any parsed [`LanguageTag`]. Ex: "pt-BR"
+    pub fn lang_code(mut self, lang_code: impl Into<LanguageTag>) -> Self {
+        self.config.lang_codes = vec![lang_code.into()];
+        self
+    }
 
-    /// set language code
-    pub fn lang_code(mut self, lang_code: LangCode) -> Self {
-        self.config.lang_code = lang_code;
+    /// set an ordered list of preferred language codes along with the [`CaptionPolicy`].
+    /// The first language having a caption track allowed by the policy is fetched.
+    pub fn languages(
+        mut self,
+        lang_codes: impl IntoIterator<Item = impl Into<LanguageTag>>,
+        policy: CaptionPolicy,
+    ) -> Self {
+        self.config.lang_codes = lang_codes.into_iter().map(Into::into).collect();
+        self.config.policy = policy;
+        self
+    }
+
+    /// translate captions into the language code through youtube when none of the languages have a caption track.
+    pub fn translate_to(mut self, lang_code: impl Into<LanguageTag>) -> Self {
+        self.config.translate_to = Some(lang_code.into());
+        self
+    }
+
+    /// set the [`TranscriptFormat`] requested from youtube's timedtext endpoint.
+    pub fn format(mut self, format: TranscriptFormat) -> Self {
+        self.config.format = format;
+        self
+    }
+
+    /// enable / disable normalization of caption text (decoding html entities and collapsing whitespace). Enabled by default.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.config.normalize = normalize;
+        self
+    }
+
+    /// strip annotations like "[Music]" and "[Applause]" from caption text while normalizing.
+    pub fn strip_markers(mut self, strip_markers: bool) -> Self {
+        self.config.strip_markers = strip_markers;
+        self
+    }
+
+    /// set the scheme and host serving the watch page and the innertube api. Ex: "http://127.0.0.1:8080"
+    pub fn watch_host(mut self, host: impl Into<String>) -> Self {
+        self.config.watch_host = host.into().trim_end_matches('/').into();
+        self
+    }
+
+    /// set the scheme and host serving the timedtext captions. Ex: "http://127.0.0.1:8080"
+    pub fn timedtext_host(mut self, host: impl Into<String>) -> Self {
+        self.config.timedtext_host = host.into().trim_end_matches('/').into();
+        self
+    }
+
+    /// set the [`Backend`] used for discovering caption tracks.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    /// use a configured http [`Client`], ignoring the proxy, user agent, accept language, consent cookie and
+    /// timeout options of the builder.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// send requests through the [`Proxy`].
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.config.http.proxy = Some(proxy);
+        self
+    }
+
+    /// set the user agent of requests. Defaults to a desktop browser.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.http.user_agent = user_agent.into();
+        self
+    }
+
+    /// set the `Accept-Language` header of requests. Defaults to "en-US,en;q=0.9".
+    pub fn accept_language(mut self, accept_language: impl Into<String>) -> Self {
+        self.config.http.accept_language = accept_language.into();
+        self
+    }
+
+    /// enable / disable the cookie accepting youtube's EU consent page. Enabled by default.
+    pub fn consent_cookie(mut self, consent_cookie: bool) -> Self {
+        self.config.http.consent_cookie = consent_cookie;
+        self
+    }
+
+    /// set the timeout of every request, `None` waits forever. Defaults to 30 seconds.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.http.timeout = timeout;
         self
     }
 
     /// Builds [`Youtube`]
+    ///
+    /// # Panics
+    /// Panics if the http client cannot be initialized, see [`YoutubeBuilder::try_build`].
     pub fn build(self) -> Youtube {
-        Youtube {
+        self.try_build().expect("Cannot initialize the http client")
+    }
+
+    /// Builds [`Youtube`], failing when the user agent or accept language is not a valid header value
+    /// or the http client cannot be initialized.
+    pub fn try_build(self) -> Result<Youtube, error::Error> {
+        let client = match self.client {
+            Some(client) => client,
+            None => self.config.http.client()?,
+        };
+        Ok(Youtube {
             config: self.config,
-        }
+            client,
+        })
     }
 }
 
@@ -65,16 +409,113 @@ impl YoutubeBuilder {
 mod test {
     use super::*;
     #[tokio::test]
-    #[ignore = "Requires mocking youtube response"]
     async fn test_u_trans() {
-        let transcript = YoutubeBuilder::default()
+        let server = crate::mock::MockServer::fixtures().await.unwrap();
+        let transcript = server
+            .builder()
             .build()
             .transcript("https://www.youtube.com/watch?v=GJLlxj_dtq8")
             .await
             .unwrap();
         assert_eq!(
-            <Transcript as Into<String>>::into(transcript),
-            "Hey, how&#39;s it going Dave 2d here?This is a Microsoft Surface go and when they first announced it I was interested in itIt seemed like a pretty compelling device having used it for a little whileI really think this is seriously the best product that Microsoft has put out in a very long time this thing starts at $400I don&#39;t think that base configuration is where you want to spend the money though. They have a mid tier one550 quite a bit more but you&#39;re getting double the RAM double the storage but significantly faster storageThat is the model that I think most people should pick up if you can afford that price bumpso this unit here, is that mid tier model the$550 unit and IReally like it. Ok, let&#39;s go around. This thing build quality is great. It&#39;s a surface productIt has a magnesium enclosure fit and finish on this is really well donetheTop surface has these new rounded edges and it actually makes the device a lot more comfortable to holdNot that the original surface products are like uncomfortableBut this small detail just makes it that much more ergonomic and that much more inviting to useIt&#39;s a nice touch and I think Microsoft should put this kind ofRounded edge on all of their products because it does make a difference. The screen is a 10 inch screenI thought I&#39;d be a little bit small for what I doBut it actually isn&#39;t it is noticeably smaller compared to like a 12 or 13 inch screen, but it doesn&#39;t feel particularly crampedIt&#39;s still a very usable surface area the bezels around that screen though are thick now visuallyIt&#39;s not attractive right having thick bezels. Like this doesn&#39;t look goodBut when you&#39;re actually using it, you won&#39;t notice it you&#39;ll be focused on your contentit&#39;s just that when this devices off or it&#39;s just sitting there and you&#39;re kind ofexamining it visually the bezels are thick the panel itself is nice its sharp great colors and brightness andHitting a price point like this with this kind of screenIt could not have been easy. Like we see four or five hundred dollar devices out there that have terrible screensThis thing looks really good. There is pen support as usual and feels relatively lag free to meI&#39;m not an artistBut the surface area feels reasonably sized for people that want to use it for any kind of digital creative workNow on the side are two speakers and they sound really good for this kind of device sizenice body to the soundExcellent stereo separation just from the positioning and you just get really clean audio that gets to a decent volumeYou also get a killer killer webcam $400 gets your webcam of this qualityit&#39;s actually one of the best kans I&#39;ve seen on any laptop period but when you compareThis webcam to something like a 12-inch MacBook. It just blows my mindI mean if you can stick a webcam like this into a $400 device, there&#39;s no excuse for other peopleThey should be using really good webcams and no one else is doing it, but surface does so good for themthis device though is not complete without the keyboard and the keyboard is aHundred bucks, which is crazy expensive you think about it. That&#39;s like at the base model. That&#39;s 20% of the cost, butThat&#39;s what we have. Okay, when it&#39;s connected up and it connects magnetically. It is an awesome. Awesomeproductivity deviceSo I was concerned that this keyboard would be really small and cramped and just kind of weird feeling because it is a lot smallerThan the regular service devices. It&#39;s not cramped. It&#39;s excellent. It does take a little bit of time to get used to itBut it is a really comfortable keyboard the trackpad feels good. It&#39;s a surface productSo tracking is accurate and gestures work nicely. But the pad is a little small. Maybe it&#39;s a visual thingI just wish there&#39;s a little bit more surface area to this trackpad. Okay performance on this device isGood, it&#39;s not amazing. It&#39;s a Pentium Gold chip and most productivity stuffLike emails web browsing or any kind of work-related stuff runs really smoothly on thisSo the drive feeds on the mid-tier model actually really good fast read speed but on the slower drive of the base model the wholeSystem is gonna feel a bit more sluggish and that reason alone makes it worth it to upgrade to the mid-tier modelBattery life is also pretty good getting around seven hours of battery life and to charge itYou can either use the included surface connect adapter or you can use the USB C portI really wish that the included adapter was USB C but its surface connect because well, that&#39;s Microsoft&#39;s for youOkay gaming performance. I was actually surprised by thisYou&#39;re not gonna be able to play some killer triple-a titlesBut light games are pretty good on this thing if you want to pick it up for some casual light gamesIt&#39;ll do the trick nowThe surface go is still a surface product through and through so if they&#39;re issues you had with surface products in the pastYou may have those same issues with this one. Like if you need more ports, there&#39;s still only one portIt&#39;s use BC this yearBut it&#39;s still only one port if you don&#39;t like the kickstand on your lapLike it&#39;s not an ideal situation for lap use, but it does work reasonablyWell plus infinite positions up to a certain degree, but I don&#39;t knowThis makes it fairly usable for most people I think but if you&#39;ve had issues in the past same issues nowOverall though really good product. I think for studentsThis is such a good option you get so much versatility on this thingYou get a great keyboard for taking notesYou can pull up course material and stuff in classGood option great for me to consumption for like a secondary device if you want it for thatI think you can&#39;t go wrong with this it is however notCheap once you add everything up together like the keyboard and like the mid tier unitIt&#39;s not the $400 device that they&#39;re kind of marketingSo you kind of have to take that into consideration, but overall I like this thing. Ok. Hope you guys enjoyed this video thumbsWe liked it subs we loved it. See you guys next time"
+            transcript
+                .transcripts
+                .iter()
+                .map(|x| x.text.as_str())
+                .collect::<String>(),
+            "Hey, how's it going Dave 2d here?This is a Microsoft Surface go and when they first announced it I was interested in itIt seemed like a pretty compelling device having used it for a little whileI really think this is seriously the best product that Microsoft has put out in a very long time this thing starts at $400I don't think that base configuration is where you want to spend the money though. They have a mid tier one550 quite a bit more but you're getting double the RAM double the storage but significantly faster storageThat is the model that I think most people should pick up if you can afford that price bumpso this unit here, is that mid tier model the$550 unit and IReally like it. Ok, let's go around. This thing build quality is great. It's a surface productIt has a magnesium enclosure fit and finish on this is really well donetheTop surface has these new rounded edges and it actually makes the device a lot more comfortable to holdNot that the original surface products are like uncomfortableBut this small detail just makes it that much more ergonomic and that much more inviting to useIt's a nice touch and I think Microsoft should put this kind ofRounded edge on all of their products because it does make a difference. The screen is a 10 inch screenI thought I'd be a little bit small for what I doBut it actually isn't it is noticeably smaller compared to like a 12 or 13 inch screen, but it doesn't feel particularly crampedIt's still a very usable surface area the bezels around that screen though are thick now visuallyIt's not attractive right having thick bezels. Like this doesn't look goodBut when you're actually using it, you won't notice it you'll be focused on your contentit's just that when this devices off or it's just sitting there and you're kind ofexamining it visually the bezels are thick the panel itself is nice its sharp great colors and brightness andHitting a price point like this with this kind of screenIt could not have been easy. Like we see four or five hundred dollar devices out there that have terrible screensThis thing looks really good. There is pen support as usual and feels relatively lag free to meI'm not an artistBut the surface area feels reasonably sized for people that want to use it for any kind of digital creative workNow on the side are two speakers and they sound really good for this kind of device sizenice body to the soundExcellent stereo separation just from the positioning and you just get really clean audio that gets to a decent volumeYou also get a killer killer webcam $400 gets your webcam of this qualityit's actually one of the best kans I've seen on any laptop period but when you compareThis webcam to something like a 12-inch MacBook. It just blows my mindI mean if you can stick a webcam like this into a $400 device, there's no excuse for other peopleThey should be using really good webcams and no one else is doing it, but surface does so good for themthis device though is not complete without the keyboard and the keyboard is aHundred bucks, which is crazy expensive you think about it. That's like at the base model. That's 20% of the cost, butThat's what we have. Okay, when it's connected up and it connects magnetically. It is an awesome. Awesomeproductivity deviceSo I was concerned that this keyboard would be really small and cramped and just kind of weird feeling because it is a lot smallerThan the regular service devices. It's not cramped. It's excellent. It does take a little bit of time to get used to itBut it is a really comfortable keyboard the trackpad feels good. It's a surface productSo tracking is accurate and gestures work nicely. But the pad is a little small. Maybe it's a visual thingI just wish there's a little bit more surface area to this trackpad. Okay performance on this device isGood, it's not amazing. It's a Pentium Gold chip and most productivity stuffLike emails web browsing or any kind of work-related stuff runs really smoothly on thisSo the drive feeds on the mid-tier model actually really good fast read speed but on the slower drive of the base model the wholeSystem is gonna feel a bit more sluggish and that reason alone makes it worth it to upgrade to the mid-tier modelBattery life is also pretty good getting around seven hours of battery life and to charge itYou can either use the included surface connect adapter or you can use the USB C portI really wish that the included adapter was USB C but its surface connect because well, that's Microsoft's for youOkay gaming performance. I was actually surprised by thisYou're not gonna be able to play some killer triple-a titlesBut light games are pretty good on this thing if you want to pick it up for some casual light gamesIt'll do the trick nowThe surface go is still a surface product through and through so if they're issues you had with surface products in the pastYou may have those same issues with this one. Like if you need more ports, there's still only one portIt's use BC this yearBut it's still only one port if you don't like the kickstand on your lapLike it's not an ideal situation for lap use, but it does work reasonablyWell plus infinite positions up to a certain degree, but I don't knowThis makes it fairly usable for most people I think but if you've had issues in the past same issues nowOverall though really good product. I think for studentsThis is such a good option you get so much versatility on this thingYou get a great keyboard for taking notesYou can pull up course material and stuff in classGood option great for me to consumption for like a secondary device if you want it for thatI think you can't go wrong with this it is however notCheap once you add everything up together like the keyboard and like the mid tier unitIt's not the $400 device that they're kind of marketingSo you kind of have to take that into consideration, but overall I like this thing. Ok. Hope you guys enjoyed this video thumbsWe liked it subs we loved it. See you guys next time"
+        );
+    }
+
+    #[test]
+    fn test_caption_tracks_errors() {
+        let youtube = YoutubeBuilder::default().build();
+        let private = r#"<script>var ytInitialPlayerResponse = {"playabilityStatus":{"status":"LOGIN_REQUIRED","reason":"This video is private"}};</script>"#;
+        assert!(matches!(
+            youtube.caption_tracks_from_text(private),
+            Err(error::Error::VideoPrivate(_))
+        ));
+        let no_captions = r#"<script>var ytInitialPlayerResponse = {"playabilityStatus":{"status":"OK"},"videoDetails":{"videoId":"GJLlxj_dtq8"}};</script>"#;
+        assert!(matches!(
+            youtube.caption_tracks_from_text(no_captions),
+            Err(error::Error::CaptionsDisabled)
+        ));
+        assert!(matches!(
+            youtube.caption_tracks_from_text("<html></html>"),
+            Err(error::Error::LayoutChanged(_))
+        ));
+    }
+
+    #[test]
+    fn test_transcript_from_timedtext() {
+        let youtube = YoutubeBuilder::default().build();
+        let xml = r#"<?xml version="1.0" encoding="utf-8" ?><transcript><text start="0" dur="1.54">Hey, how&amp;#39;s it going Dave 2d here?</text></transcript>"#;
+        let transcript = youtube.transcript_from_timedtext(xml).unwrap();
+        assert_eq!(
+            transcript.transcripts[0].text,
+            "Hey, how's it going Dave 2d here?"
         );
+        let srv3 = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3"><body><p t="160" d="3120">hey how's it going</p></body></timedtext>"#;
+        let transcript = youtube.transcript_from_timedtext(srv3).unwrap();
+        assert_eq!(transcript.transcripts[0].start, Duration::from_millis(160));
+        let json3 = r#"{"events":[{"tStartMs":160,"dDurationMs":3120,"segs":[{"utf8":"hey how's it going"}]}]}"#;
+        let transcript = youtube.transcript_from_timedtext(json3).unwrap();
+        assert_eq!(transcript.transcripts.len(), 1);
+        assert!(youtube.transcript_from_timedtext("<html></html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_fetches() {
+        let server = crate::mock::MockServer::fixtures().await.unwrap();
+        let youtube = server.builder().backend(Backend::Innertube).build();
+        let link = "https://youtu.be/GJLlxj_dtq8";
+        let metadata = youtube.metadata(link).await.unwrap();
+        assert_eq!(metadata.title, "Microsoft Surface Go - Review");
+        let tracks = youtube.caption_tracks(link).await.unwrap();
+        assert!(tracks.iter().any(|x| x.lang_code == "en"));
+        // served by the player api, without falling back to the watch page
+        assert_eq!(server.hits("/youtubei/v1/player"), 2);
+        assert_eq!(server.hits("/watch"), 0);
+        assert!(matches!(
+            youtube.transcript("aaaaaaaaaaa").await,
+            Err(error::Error::Network(_))
+        ));
+    }
+
+    /// fixtures directory serving the shipped watch page along the player response provided.
+    fn player_fixtures(name: &str, player: &str) -> std::path::PathBuf {
+        let fixtures = std::env::temp_dir().join(format!("youtube-transcript-{name}"));
+        let dir = fixtures.join("GJLlxj_dtq8");
+        std::fs::create_dir_all(&dir).unwrap();
+        let watch = std::path::Path::new(crate::mock::FIXTURES).join("GJLlxj_dtq8/watch.html");
+        std::fs::copy(watch, dir.join("watch.html")).unwrap();
+        std::fs::write(dir.join("player.json"), player).unwrap();
+        fixtures
+    }
+
+    #[tokio::test]
+    async fn test_innertube_fallback() {
+        let fixtures = player_fixtures("fallback", "<html>");
+        let server = crate::mock::MockServer::start(&fixtures).await.unwrap();
+        let youtube = server.builder().backend(Backend::Innertube).build();
+        let tracks = youtube.caption_tracks("GJLlxj_dtq8").await.unwrap();
+        assert!(tracks.iter().any(|x| x.lang_code == "en"));
+        assert_eq!(server.hits("/youtubei/v1/player"), 1);
+        assert_eq!(server.hits("/watch"), 1);
+
+        let player =
+            r#"{"playabilityStatus":{"status":"LOGIN_REQUIRED","reason":"This video is private"}}"#;
+        let fixtures = player_fixtures("private", player);
+        let server = crate::mock::MockServer::start(&fixtures).await.unwrap();
+        let youtube = server.builder().backend(Backend::Innertube).build();
+        assert!(matches!(
+            youtube.caption_tracks("GJLlxj_dtq8").await,
+            Err(error::Error::VideoPrivate(_))
+        ));
+        assert!(matches!(
+            youtube.metadata("GJLlxj_dtq8").await,
+            Err(error::Error::VideoPrivate(_))
+        ));
+        assert_eq!(server.hits("/watch"), 0);
     }
 }